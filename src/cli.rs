@@ -0,0 +1,82 @@
+use std::collections::HashMap;
+
+// Command line of a subcommand: positional words, '--name value' options and '--name' flags
+#[derive(Debug)]
+pub struct Args {
+    positional: Vec<String>,
+    options: HashMap<String, String>,
+    flags: Vec<String>,
+}
+
+impl Args {
+    // Only the listed options and flags are accepted, '--name=value' works as well
+    pub fn parse(args: &[String], options: &[&str], flags: &[&str]) -> Result<Args, String> {
+        let mut result = Args {
+            positional: Vec::new(),
+            options: HashMap::new(),
+            flags: Vec::new(),
+        };
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            let Some(name) = arg.strip_prefix("--") else {
+                result.positional.push(arg.clone());
+                continue;
+            };
+            let (name, inline) = match name.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (name, None),
+            };
+            if flags.contains(&name) && inline.is_none() {
+                result.flags.push(name.to_string());
+            } else if options.contains(&name) {
+                let value = match inline {
+                    Some(value) => value,
+                    None => iter
+                        .next()
+                        .ok_or_else(|| format!("option '--{}' needs a value", name))?
+                        .clone(),
+                };
+                result.options.insert(name.to_string(), value);
+            } else {
+                return Err(format!("unknown option '--{}'", name));
+            }
+        }
+        Ok(result)
+    }
+
+    pub fn positional(&self) -> &[String] {
+        &self.positional
+    }
+
+    pub fn option(&self, name: &str) -> Option<&str> {
+        self.options.get(name).map(String::as_str)
+    }
+
+    pub fn flag(&self, name: &str) -> bool {
+        self.flags.iter().any(|x| x == name)
+    }
+}
+
+#[cfg(test)]
+fn strings(args: &[&str]) -> Vec<String> {
+    args.iter().map(|x| x.to_string()).collect()
+}
+
+#[test]
+fn test_args() {
+    let args = strings(&["file.dat", "--op", "max", "--all", "--format=csv", "x"]);
+    let args = Args::parse(&args, &["op", "format", "expr"], &["all"]).unwrap();
+    assert_eq!(args.positional(), ["file.dat", "x"]);
+    assert_eq!(args.option("op"), Some("max"));
+    assert_eq!(args.option("format"), Some("csv"));
+    assert_eq!(args.option("expr"), None);
+    assert!(args.flag("all"));
+    assert!(!args.flag("none"));
+
+    let error = Args::parse(&strings(&["--what"]), &["op"], &[]).unwrap_err();
+    assert_eq!(error, "unknown option '--what'");
+    let error = Args::parse(&strings(&["--op"]), &["op"], &[]).unwrap_err();
+    assert_eq!(error, "option '--op' needs a value");
+    let error = Args::parse(&strings(&["--all=yes"]), &[], &["all"]).unwrap_err();
+    assert_eq!(error, "unknown option '--all'");
+}
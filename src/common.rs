@@ -18,3 +18,27 @@ lazy_static::lazy_static! {
         WORDS_DATA.lines().collect()
     };
 }
+
+// Small deterministic xorshift generator, enough for randomized tests
+// and the random probes of the statistics
+pub struct XorShift {
+    state: u64,
+}
+
+impl XorShift {
+    pub fn new(seed: u64) -> XorShift {
+        XorShift { state: seed.max(1) }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    // Uniform enough in [0, bound) for bounds much smaller than 2^64
+    pub fn below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound
+    }
+}
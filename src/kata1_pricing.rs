@@ -1,12 +1,11 @@
 #![cfg(test)]
 
-use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::fmt;
 
 const STD_DENOMINATOR: u64 = 100;
 
-#[derive(Copy, Clone, Eq, PartialEq, PartialOrd, Hash, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 struct Money {
     value: u64,
 }
@@ -47,12 +46,6 @@ impl std::ops::Mul<usize> for Money {
     }
 }
 
-impl Ord for Money {
-    fn cmp(&self, other: &Self) -> Ordering {
-        self.value.cmp(&other.value)
-    }
-}
-
 impl fmt::Display for Money {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.value / STD_DENOMINATOR)?;
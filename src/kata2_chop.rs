@@ -1,6 +1,10 @@
 #![cfg(test)]
 
+use crate::common::{XorShift, WORDS_DATA, WORDS_FILE, WORDS_PLAIN};
+
 use std::cmp::Ordering;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Cursor, Read, Seek, SeekFrom, Write};
 
 fn test_chop(chop: &dyn Fn(i32, &[i32]) -> isize, name: &str) {
     println!("{}", name);
@@ -50,14 +54,14 @@ fn chop_recursive_iter(needle: i32, silo: &[i32], start: usize, end: usize) -> i
     let middle = (start + end) / 2;
     let element = silo[middle];
     match needle.cmp(&element) {
-        Ordering::Less => chop_recursive_iter(needle, &silo, start, middle),
-        Ordering::Greater => chop_recursive_iter(needle, &silo, middle + 1, end),
-        Ordering::Equal => return middle as isize,
+        Ordering::Less => chop_recursive_iter(needle, silo, start, middle),
+        Ordering::Greater => chop_recursive_iter(needle, silo, middle + 1, end),
+        Ordering::Equal => middle as isize,
     }
 }
 
 fn chop_recursive(needle: i32, silo: &[i32]) -> isize {
-    chop_recursive_iter(needle, &silo, 0, silo.len())
+    chop_recursive_iter(needle, silo, 0, silo.len())
 }
 
 fn chop_functional_iter(needle: i32, silo: &[i32], offset: usize) -> isize {
@@ -69,7 +73,7 @@ fn chop_functional_iter(needle: i32, silo: &[i32], offset: usize) -> isize {
 
     let current = (start + end) / 2;
     match needle.cmp(&silo[current]) {
-        Ordering::Less => chop_functional_iter(needle, &silo[..current], 0),
+        Ordering::Less => chop_functional_iter(needle, &silo[..current], offset),
         Ordering::Greater => {
             chop_functional_iter(needle, &silo[current + 1..], offset + current + 1)
         }
@@ -174,3 +178,542 @@ fn test_chops() {
     test_chop(&chop_functional_clean, "Clean");
     test_chop(&chop_functional_tail, "Tail");
 }
+
+///////////////////////////////////////////////////////////
+// External sorted data: the silo is not in memory, so we seek to byte offsets
+
+// Fixed-width records: every element is stored as 4 little-endian bytes
+const RECORD_SIZE: u64 = 4;
+
+fn read_record<R: Read + Seek>(source: &mut R, index: u64) -> i32 {
+    let mut buffer = [0u8; RECORD_SIZE as usize];
+    source.seek(SeekFrom::Start(index * RECORD_SIZE)).unwrap();
+    source.read_exact(&mut buffer).unwrap();
+    i32::from_le_bytes(buffer)
+}
+
+fn write_records<W: Write>(sink: &mut W, silo: &[i32]) {
+    for element in silo {
+        sink.write_all(&element.to_le_bytes()).unwrap();
+    }
+}
+
+// The same loop as 'chop_iterative', only the element access differs
+fn chop_records<R: Read + Seek>(needle: i32, source: &mut R) -> isize {
+    let mut start = 0;
+    let mut end = source.seek(SeekFrom::End(0)).unwrap() / RECORD_SIZE;
+    while start < end {
+        let current = (start + end) / 2;
+        match needle.cmp(&read_record(source, current)) {
+            Ordering::Equal => return current as isize,
+            Ordering::Less => end = current,
+            Ordering::Greater => start = current + 1,
+        }
+    }
+    -1
+}
+
+// Read the line starting at 'pos' without its line ending (like 'str::lines'),
+// the returned length includes the ending so 'pos + len' is the next line start
+fn read_line_at<R: BufRead + Seek + ?Sized>(source: &mut R, pos: u64, line: &mut Vec<u8>) -> u64 {
+    line.clear();
+    source.seek(SeekFrom::Start(pos)).unwrap();
+    let len = source.read_until(b'\n', line).unwrap() as u64;
+    if line.last() == Some(&b'\n') {
+        line.pop();
+        if line.last() == Some(&b'\r') {
+            line.pop();
+        }
+    }
+    len
+}
+
+// Newline-delimited text: the search works on byte offsets and realigns every probe
+// to the start of the next line, so the result is the offset of the matching line.
+// A memory-mapped file is searched the same way through 'Cursor<&[u8]>'.
+fn chop_lines<R: BufRead + Seek + ?Sized>(needle: &str, source: &mut R) -> Option<u64> {
+    let mut line = Vec::new();
+
+    // 'start' is always a line start, the matching line (if any) starts in [start, end)
+    let mut start = 0;
+    let mut end = source.seek(SeekFrom::End(0)).unwrap();
+    while start < end {
+        let middle = (start + end) / 2;
+        let mut line_start = if middle == start {
+            start
+        } else {
+            // Skip the tail of the line containing 'middle - 1'
+            middle - 1 + read_line_at(source, middle - 1, &mut line)
+        };
+        if line_start >= end {
+            // No line begins in the upper half, probe the first one instead
+            line_start = start;
+        }
+
+        let line_len = read_line_at(source, line_start, &mut line);
+        match needle.as_bytes().cmp(&line) {
+            Ordering::Equal => return Some(line_start),
+            Ordering::Less => end = line_start,
+            Ordering::Greater => start = line_start + line_len,
+        }
+    }
+    None
+}
+
+// Adapters to run the external searches through the common 'test_chop' table
+
+fn chop_records_cursor(needle: i32, silo: &[i32]) -> isize {
+    let mut cursor = Cursor::new(Vec::new());
+    write_records(&mut cursor, silo);
+    chop_records(needle, &mut cursor)
+}
+
+// Zero-padded numbers keep the text order equal to the numeric order
+const LINE_WIDTH: u64 = 6;
+
+fn chop_lines_cursor(needle: i32, silo: &[i32]) -> isize {
+    let text: String = silo.iter().map(|x| format!("{:05}\n", x)).collect();
+    let needle = format!("{:05}", needle);
+    match chop_lines(&needle, &mut Cursor::new(text.as_bytes())) {
+        Some(offset) => (offset / LINE_WIDTH) as isize,
+        None => -1,
+    }
+}
+
+#[test]
+fn test_external_chops() {
+    test_chop(&chop_records_cursor, "Records");
+    test_chop(&chop_lines_cursor, "Lines");
+}
+
+#[test]
+fn test_records_file() {
+    let silo: Vec<i32> = (-500..500).map(|x| x * 3).collect();
+    let filename = std::env::temp_dir().join(format!("chop_records_{}.bin", std::process::id()));
+    write_records(&mut File::create(&filename).unwrap(), &silo);
+
+    let mut file = File::open(&filename).unwrap();
+    for needle in -1510..1510 {
+        let expected = chop_iterative(needle, &silo);
+        assert_eq!(expected, chop_records(needle, &mut file), "{}", needle);
+    }
+    std::fs::remove_file(&filename).unwrap();
+}
+
+#[test]
+fn test_wordlist_file() {
+    let mut needles: Vec<&str> = WORDS_PLAIN.iter().copied().step_by(997).collect();
+    needles.extend([WORDS_PLAIN[0], WORDS_PLAIN[WORDS_PLAIN.len() - 1]]);
+    needles.extend([
+        "",
+        "0",
+        "A",
+        "basic",
+        "baaac",
+        "tree",
+        "teee",
+        "zzzzzz",
+        "\u{10ffff}",
+    ]);
+
+    let mut file = BufReader::new(File::open(&*WORDS_FILE).unwrap());
+    let mut mapped = Cursor::new(WORDS_DATA.as_bytes());
+    let mut line = Vec::new();
+    for needle in needles {
+        let expected = WORDS_PLAIN.binary_search(&needle).is_ok();
+        for source in [&mut file as &mut dyn BufReadSeek, &mut mapped] {
+            let found = chop_lines(needle, source);
+            assert_eq!(expected, found.is_some(), "{}", needle);
+            if let Some(offset) = found {
+                read_line_at(source, offset, &mut line);
+                assert_eq!(needle.as_bytes(), line, "{}", needle);
+            }
+        }
+    }
+}
+
+trait BufReadSeek: BufRead + Seek {}
+impl<T: BufRead + Seek> BufReadSeek for T {}
+
+///////////////////////////////////////////////////////////
+// Rotated sorted array: [5, 7, 1, 3] is [1, 3, 5, 7] rotated by 2
+
+fn test_chop_rotated(chop: &dyn Fn(i32, &[i32]) -> isize, name: &str) {
+    println!("{}", name);
+
+    assert_eq!(-1, chop(3, &[]));
+    assert_eq!(0, chop(1, &[1]));
+    assert_eq!(-1, chop(2, &[1]));
+
+    // not rotated at all
+    assert_eq!(0, chop(1, &[1, 3, 5, 7]));
+    assert_eq!(3, chop(7, &[1, 3, 5, 7]));
+    assert_eq!(-1, chop(4, &[1, 3, 5, 7]));
+
+    assert_eq!(0, chop(3, &[3, 1]));
+    assert_eq!(1, chop(1, &[3, 1]));
+    assert_eq!(-1, chop(2, &[3, 1]));
+
+    assert_eq!(0, chop(5, &[5, 7, 1, 3]));
+    assert_eq!(1, chop(7, &[5, 7, 1, 3]));
+    assert_eq!(2, chop(1, &[5, 7, 1, 3]));
+    assert_eq!(3, chop(3, &[5, 7, 1, 3]));
+    assert_eq!(-1, chop(0, &[5, 7, 1, 3]));
+    assert_eq!(-1, chop(4, &[5, 7, 1, 3]));
+    assert_eq!(-1, chop(6, &[5, 7, 1, 3]));
+    assert_eq!(-1, chop(8, &[5, 7, 1, 3]));
+
+    // pivot at the very end
+    assert_eq!(4, chop(1, &[3, 5, 7, 9, 1]));
+    assert_eq!(3, chop(9, &[3, 5, 7, 9, 1]));
+    assert_eq!(-1, chop(2, &[3, 5, 7, 9, 1]));
+}
+
+// Index of the smallest element, which is where the rotation starts (distinct elements)
+fn find_pivot(silo: &[i32]) -> usize {
+    let mut start = 0;
+    let mut end = silo.len() - 1;
+    while start < end {
+        let current = (start + end) / 2;
+        if silo[current] > silo[end] {
+            start = current + 1; // the drop is to the right
+        } else {
+            end = current;
+        }
+    }
+    start
+}
+
+fn chop_rotated(needle: i32, silo: &[i32]) -> isize {
+    if silo.is_empty() {
+        return -1;
+    }
+    let pivot = find_pivot(silo);
+    let (base, part) = if needle >= silo[0] && pivot > 0 {
+        (0, &silo[..pivot])
+    } else {
+        (pivot, &silo[pivot..])
+    };
+    match chop_iterative(needle, part) {
+        -1 => -1,
+        pos => (base as isize) + pos,
+    }
+}
+
+#[test]
+fn test_pivot() {
+    assert_eq!(0, find_pivot(&[1]));
+    assert_eq!(0, find_pivot(&[1, 3, 5, 7]));
+    assert_eq!(1, find_pivot(&[3, 1]));
+    assert_eq!(2, find_pivot(&[5, 7, 1, 3]));
+    assert_eq!(3, find_pivot(&[5, 7, 9, 1, 3]));
+    assert_eq!(4, find_pivot(&[3, 5, 7, 9, 1]));
+}
+
+#[test]
+fn test_rotated_chops() {
+    test_chop_rotated(&chop_rotated, "Rotated");
+
+    // rotated by zero is the ordinary case
+    test_chop(&chop_rotated, "Rotated by zero");
+}
+
+///////////////////////////////////////////////////////////
+// Matrix sorted by rows and by columns
+
+type Position = Option<(usize, usize)>;
+
+fn test_chop_matrix(chop: &dyn Fn(i32, &[&[i32]]) -> Position, name: &str) {
+    println!("{}", name);
+
+    assert_eq!(None, chop(3, &[]));
+    assert_eq!(None, chop(3, &[&[]]));
+    assert_eq!(Some((0, 0)), chop(1, &[&[1]]));
+    assert_eq!(None, chop(2, &[&[1]]));
+
+    // single row and single column
+    assert_eq!(Some((0, 2)), chop(5, &[&[1, 3, 5]]));
+    assert_eq!(None, chop(4, &[&[1, 3, 5]]));
+    assert_eq!(Some((2, 0)), chop(5, &[&[1], &[3], &[5]]));
+    assert_eq!(None, chop(4, &[&[1], &[3], &[5]]));
+
+    let matrix: [&[i32]; 3] = [&[1, 4, 7, 11], &[2, 5, 8, 12], &[3, 6, 9, 16]];
+    assert_eq!(Some((0, 0)), chop(1, &matrix));
+    assert_eq!(Some((0, 3)), chop(11, &matrix));
+    assert_eq!(Some((2, 0)), chop(3, &matrix));
+    assert_eq!(Some((2, 3)), chop(16, &matrix));
+    assert_eq!(Some((1, 1)), chop(5, &matrix));
+    assert_eq!(Some((2, 2)), chop(9, &matrix));
+    assert_eq!(None, chop(0, &matrix));
+    assert_eq!(None, chop(10, &matrix));
+    assert_eq!(None, chop(13, &matrix));
+    assert_eq!(None, chop(17, &matrix));
+}
+
+// Start at the top-right corner: every comparison drops either a row or a column
+fn chop_matrix(needle: i32, matrix: &[&[i32]]) -> Position {
+    let mut row = 0;
+    let mut col = matrix.first()?.len();
+    while row < matrix.len() && col > 0 {
+        match needle.cmp(&matrix[row][col - 1]) {
+            Ordering::Equal => return Some((row, col - 1)),
+            Ordering::Less => col -= 1,
+            Ordering::Greater => row += 1,
+        }
+    }
+    None
+}
+
+// Binary search of every row, O(rows * log(cols))
+fn chop_matrix_rows(needle: i32, matrix: &[&[i32]]) -> Position {
+    matrix
+        .iter()
+        .enumerate()
+        .take_while(|(_, row)| row.first().is_some_and(|&first| first <= needle))
+        .find_map(|(i, row)| match chop_iterative(needle, row) {
+            -1 => None,
+            pos => Some((i, pos as usize)),
+        })
+}
+
+#[test]
+fn test_matrix_chops() {
+    test_chop_matrix(&chop_matrix, "Staircase");
+    test_chop_matrix(&chop_matrix_rows, "Rows");
+}
+
+///////////////////////////////////////////////////////////
+// Monotonic predicate: find the first x where f(x) becomes true
+
+type Predicate<'a> = dyn Fn(i64) -> bool + 'a;
+
+fn test_first_true(first_true: &dyn Fn(i64, i64, &Predicate<'_>) -> i64, name: &str) {
+    println!("{}", name);
+
+    // empty range returns its end
+    assert_eq!(5, first_true(5, 5, &|_| true));
+    assert_eq!(5, first_true(5, 5, &|_| false));
+
+    // always and never true
+    assert_eq!(0, first_true(0, 10, &|_| true));
+    assert_eq!(10, first_true(0, 10, &|_| false));
+
+    assert_eq!(1, first_true(0, 10, &|x| x >= 1));
+    assert_eq!(9, first_true(0, 10, &|x| x >= 9));
+    assert_eq!(-3, first_true(-10, 10, &|x| x >= -3));
+
+    // integer square root of 1000 is 31
+    assert_eq!(32, first_true(0, 1000, &|x| x * x > 1000));
+
+    // whole domain does not overflow in the middle computation
+    assert_eq!(0, first_true(i64::MIN, i64::MAX, &|x| x >= 0));
+    assert_eq!(
+        i64::MAX - 1,
+        first_true(i64::MIN, i64::MAX, &|x| x >= i64::MAX - 1)
+    );
+    assert_eq!(
+        i64::MIN + 1,
+        first_true(i64::MIN, i64::MAX, &|x| x > i64::MIN)
+    );
+}
+
+// Returns 'end' if the predicate is false everywhere in [start, end)
+fn first_true(start: i64, end: i64, pred: &Predicate<'_>) -> i64 {
+    let mut start = start;
+    let mut end = end;
+    while start < end {
+        let current = start + ((end as i128 - start as i128) / 2) as i64;
+        if pred(current) {
+            end = current;
+        } else {
+            start = current + 1;
+        }
+    }
+    start
+}
+
+// Bisection over floats stops when the interval is 'eps' wide,
+// the result is the upper bound where the predicate is known to hold
+fn first_true_float(start: f64, end: f64, eps: f64, pred: &dyn Fn(f64) -> bool) -> f64 {
+    let mut start = start;
+    let mut end = end;
+    while end - start > eps {
+        let current = start + (end - start) / 2.0;
+        if current <= start || current >= end {
+            break; // no representable float between the bounds
+        }
+        if pred(current) {
+            end = current;
+        } else {
+            start = current;
+        }
+    }
+    end
+}
+
+#[test]
+fn test_predicate_chops() {
+    test_first_true(&first_true, "Predicate");
+
+    // The slice search is the predicate search over the indexes
+    let chop_by_predicate = |needle: i32, silo: &[i32]| {
+        let end = silo.len() as i64;
+        let pos = first_true(0, end, &|x| silo[x as usize] >= needle);
+        if pos < end && silo[pos as usize] == needle {
+            pos as isize
+        } else {
+            -1
+        }
+    };
+    test_chop(&chop_by_predicate, "Predicate slice");
+}
+
+#[test]
+fn test_predicate_float() {
+    let close = |a: f64, b: f64| (a - b).abs() < 1e-9;
+
+    assert!(close(
+        2f64.sqrt(),
+        first_true_float(0.0, 2.0, 1e-12, &|x| x * x >= 2.0)
+    ));
+    assert!(close(0.5, first_true_float(0.0, 1.0, 1e-12, &|x| x >= 0.5)));
+    assert!(close(
+        -1.0,
+        first_true_float(-3.0, 3.0, 1e-12, &|x| x.powi(3) >= -1.0)
+    ));
+
+    // always and never true
+    assert!(close(0.0, first_true_float(0.0, 1.0, 1e-12, &|_| true)));
+    assert!(close(1.0, first_true_float(0.0, 1.0, 1e-12, &|_| false)));
+
+    // too small epsilon must not loop forever
+    assert!(close(1.0, first_true_float(0.0, 1.0, 0.0, &|x| x >= 1.0)));
+    assert_eq!(1e300, first_true_float(1e300, 1e300, 1e-12, &|_| true));
+}
+
+///////////////////////////////////////////////////////////
+// Randomized differential check: every variant against a linear scan
+
+type Chop = fn(i32, &[i32]) -> isize;
+
+const CHOPS: [(&str, Chop); 10] = [
+    ("Iter", chop_iterative),
+    ("Recr", chop_recursive),
+    ("Func", chop_functional),
+    ("Pure", chop_functional_pure),
+    ("Clean", chop_functional_clean),
+    ("Tail", chop_functional_tail),
+    ("Rotated", chop_rotated),
+    ("Records", chop_records_cursor),
+    ("Lines", chop_lines_cursor),
+    ("Element", chop_element_index),
+];
+
+// The element variant only tells presence, so any valid index will do
+fn chop_element_index(needle: i32, silo: &[i32]) -> isize {
+    match chop_functional_element(needle, silo) {
+        Some(_) => silo.iter().position(|&x| x == needle).unwrap() as isize,
+        None => -1,
+    }
+}
+
+// With duplicates any index of an equal element is a right answer
+fn chop_agrees(chop: Chop, needle: i32, silo: &[i32]) -> bool {
+    let present = silo.contains(&needle);
+    match chop(needle, silo) {
+        -1 => !present,
+        pos => pos >= 0 && silo.get(pos as usize) == Some(&needle),
+    }
+}
+
+// Drop runs of elements, halving the run length, while the disagreement persists
+fn shrink_silo(chop: Chop, needle: i32, silo: &[i32]) -> Vec<i32> {
+    let mut silo = silo.to_vec();
+    let mut run = silo.len().max(1);
+    loop {
+        let len_before = silo.len();
+        let mut index = 0;
+        while index < silo.len() {
+            let mut smaller = silo.clone();
+            smaller.drain(index..(index + run).min(silo.len()));
+            if chop_agrees(chop, needle, &smaller) {
+                index += run;
+            } else {
+                silo = smaller;
+            }
+        }
+        if run > 1 {
+            run /= 2;
+        } else if silo.len() == len_before {
+            return silo;
+        }
+    }
+}
+
+fn check_all_chops(needle: i32, silo: &[i32]) {
+    for (name, chop) in CHOPS {
+        if !chop_agrees(chop, needle, silo) {
+            let silo = shrink_silo(chop, needle, silo);
+            panic!(
+                "{} disagrees with linear scan: chop({}, {:?}) = {}",
+                name,
+                needle,
+                silo,
+                chop(needle, &silo)
+            );
+        }
+    }
+}
+
+// Values stay in [0, 100000) for the zero-padded 'Lines' adapter
+fn random_silo(rng: &mut XorShift, len: usize, spread: u64) -> Vec<i32> {
+    let mut silo: Vec<i32> = (0..len).map(|_| rng.below(spread) as i32).collect();
+    silo.sort_unstable();
+    silo
+}
+
+#[test]
+fn test_chops_random() {
+    let mut rng = XorShift::new(0x5eed_c0de);
+    for round in 0..600 {
+        let len = rng.below(40) as usize;
+        // Narrow spread gives lots of duplicates, wide one mostly distinct values
+        let spread = if round % 2 == 0 {
+            len as u64 / 2 + 1
+        } else {
+            99_999
+        };
+        let mut silo = random_silo(&mut rng, len, spread);
+        if round % 2 == 1 {
+            silo.dedup();
+        }
+
+        for &element in &silo {
+            check_all_chops(element, &silo);
+            check_all_chops(element - 1, &silo);
+            check_all_chops(element + 1, &silo);
+        }
+        check_all_chops(rng.below(spread + 2) as i32 - 1, &silo);
+    }
+}
+
+#[test]
+fn test_chops_random_huge() {
+    let mut rng = XorShift::new(0xb16_5105);
+    let silo = random_silo(&mut rng, 100_000, 99_999);
+    for _ in 0..50 {
+        check_all_chops(rng.below(100_001) as i32 - 1, &silo);
+    }
+    check_all_chops(silo[0], &silo);
+    check_all_chops(silo[silo.len() - 1], &silo);
+}
+
+#[test]
+fn test_shrink_reports_smallest() {
+    // A broken chop that never finds the last element
+    let broken: Chop = |needle, silo| match chop_iterative(needle, silo) {
+        pos if pos >= 0 && pos as usize == silo.len() - 1 => -1,
+        pos => pos,
+    };
+    assert_eq!(vec![5], shrink_silo(broken, 5, &[1, 2, 3, 4, 5]));
+}
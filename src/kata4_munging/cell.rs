@@ -0,0 +1,113 @@
+// Cell text split into the value and the flag after it: "97*" is 97 flagged by '*'
+#[derive(Clone, PartialEq, Debug)]
+pub struct Cell {
+    pub value: Value,
+    pub flag: Option<char>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Value {
+    Missing,
+    Integer(i64),
+    Decimal(f64),
+    Text(String),
+}
+
+// Kind of the value, used to tell the summary rows from the data rows
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Kind {
+    Missing,
+    Number,
+    Text,
+}
+
+// A flag is a punctuation sign after a number, the decimal point is not a flag
+fn is_flag(c: char) -> bool {
+    c.is_ascii_punctuation() && c != '.'
+}
+
+fn parse_number(text: &str) -> Option<Value> {
+    if let Ok(value) = text.parse() {
+        Some(Value::Integer(value))
+    } else if text.contains(|c: char| c.is_ascii_digit()) {
+        // Reject 'inf' and 'NaN', they are words in a data file
+        text.parse().ok().map(Value::Decimal)
+    } else {
+        None
+    }
+}
+
+// Strip the flag if what is left is a number, so that "A's" or "-" stay text
+pub fn split_flag(text: &str) -> (&str, Option<char>) {
+    match text.chars().last() {
+        Some(flag) if is_flag(flag) && text.len() > 1 => {
+            let rest = &text[..text.len() - flag.len_utf8()];
+            match parse_number(rest) {
+                Some(_) => (rest, Some(flag)),
+                None => (text, None),
+            }
+        }
+        _ => (text, None),
+    }
+}
+
+impl Cell {
+    pub fn parse(text: Option<&str>) -> Cell {
+        let Some(text) = text else {
+            return Cell {
+                value: Value::Missing,
+                flag: None,
+            };
+        };
+        let (rest, flag) = split_flag(text);
+        let value = parse_number(rest).unwrap_or_else(|| Value::Text(rest.to_string()));
+        Cell { value, flag }
+    }
+
+    pub fn kind(&self) -> Kind {
+        match self.value {
+            Value::Missing => Kind::Missing,
+            Value::Integer(_) | Value::Decimal(_) => Kind::Number,
+            Value::Text(_) => Kind::Text,
+        }
+    }
+}
+
+#[cfg(test)]
+impl Cell {
+    pub fn number(&self) -> Option<f64> {
+        match self.value {
+            Value::Integer(value) => Some(value as f64),
+            Value::Decimal(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+#[test]
+fn test_cells() {
+    let cell = |text| Cell::parse(Some(text));
+
+    assert_eq!(cell("97").value, Value::Integer(97));
+    assert_eq!(cell("97").flag, None);
+    assert_eq!(cell("97*").value, Value::Integer(97));
+    assert_eq!(cell("97*").flag, Some('*'));
+    assert_eq!(cell("-3").value, Value::Integer(-3));
+    assert_eq!(cell("82.9").value, Value::Decimal(82.9));
+    assert_eq!(cell("82.9").flag, None);
+    assert_eq!(cell("0.5!").value, Value::Decimal(0.5));
+    assert_eq!(cell("0.5!").flag, Some('!'));
+    assert_eq!(cell("1004.5").number(), Some(1004.5));
+
+    // Words stay words, with all their punctuation
+    assert_eq!(cell("mo").value, Value::Text("mo".to_string()));
+    assert_eq!(cell("A's").value, Value::Text("A's".to_string()));
+    assert_eq!(cell("-").value, Value::Text("-".to_string()));
+    assert_eq!(cell("*").value, Value::Text("*".to_string()));
+    assert_eq!(cell("inf").value, Value::Text("inf".to_string()));
+    assert_eq!(cell("RTFH").kind(), Kind::Text);
+    assert_eq!(cell("mo").number(), None);
+
+    assert_eq!(Cell::parse(None).value, Value::Missing);
+    assert_eq!(Cell::parse(None).kind(), Kind::Missing);
+}
@@ -0,0 +1,734 @@
+use super::error::MungeError;
+use super::formats;
+use super::query::{col, Answer, BadRows, Condition, Expr, Fold, Query};
+use super::stream::{self, Rows};
+use super::table::{Column, Record, Table};
+use crate::cli::Args;
+
+use std::cmp::Ordering;
+use std::io::{BufRead, Write};
+use std::path::Path;
+use std::process::ExitCode;
+
+pub const USAGE: &str = "\
+Usage: codekata munge FILE --op OP (--columns A[,B] | --expr EXPR) [options]
+
+Operations:
+  min-spread       rows with the smallest abs(A - B)
+  min, max         rows with the smallest or the largest value, all the ties
+  top              the --k rows with the largest values
+  sum, avg, count  aggregates over all rows or per --group-by column
+
+Options:
+  --where EXPR     only the rows where EXPR holds, like 'F - A > 10'
+  --group-by COL   aggregate per value of the column
+  --k N            number of rows for 'top', 10 by default
+  --format FMT     text, csv or json, text by default
+  --input FMT      fixed, csv, tsv or jsonl, by the extension by default
+  --skip-bad-rows  warn about rows with bad values instead of stopping
+
+FILE is a fixed-width table like weather.dat, or .csv, .tsv, .jsonl, any of
+them may be gzipped as .gz. FILE '-' is the standard input.";
+
+// Result as a small table of texts, so that every format prints it the same way
+struct Output {
+    columns: Vec<String>,
+    rows: Vec<Vec<Option<String>>>,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+enum Format {
+    Text,
+    Csv,
+    Json,
+}
+
+fn parse_format(text: Option<&str>) -> Result<Format, String> {
+    match text {
+        None | Some("text") => Ok(Format::Text),
+        Some("csv") => Ok(Format::Csv),
+        Some("json") => Ok(Format::Json),
+        Some(other) => Err(format!("unknown format '{}'", other)),
+    }
+}
+
+// min-spread needs two columns, the others use one column or the expression
+fn parse_expr(args: &Args, op: &str) -> Result<Expr, String> {
+    let columns: Option<Vec<&str>> = args.option("columns").map(|x| x.split(',').collect());
+    match (columns.as_deref(), args.option("expr")) {
+        (Some(_), Some(_)) => Err("use either --columns or --expr".to_string()),
+        (Some(&[a, b]), None) if op == "min-spread" => Ok((col(a) - col(b)).abs()),
+        (Some(_), None) if op == "min-spread" => {
+            Err("min-spread needs two columns, like --columns MxT,MnT".to_string())
+        }
+        (Some(&[a]), None) => Ok(col(a)),
+        (Some(_), None) => Err(format!("{} needs one column", op)),
+        (None, Some(expr)) => expr.parse(),
+        (None, None) if op == "count" => Ok(Expr::Const(1.0)),
+        (None, None) => Err("--columns or --expr is needed".to_string()),
+    }
+}
+
+fn number_text(value: f64) -> String {
+    value.to_string()
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+enum Op {
+    Min,
+    Max,
+    Top(usize),
+    Sum,
+    Avg,
+    Count,
+}
+
+// What the command line asks for, the same for a table and for a stream
+struct Plan<'a> {
+    op: Op,
+    name: &'a str,
+    expr: Expr,
+    condition: Option<Condition>,
+    group_by: Option<&'a str>,
+    bad_rows: BadRows,
+}
+
+fn parse_plan(args: &Args) -> Result<Plan<'_>, String> {
+    let name = args.option("op").ok_or("--op is needed")?;
+    let expr = parse_expr(args, name)?;
+    let op = match name {
+        "min-spread" | "min" => Op::Min,
+        "max" => Op::Max,
+        "top" => Op::Top(match args.option("k") {
+            Some(k) => k.parse().map_err(|_| format!("bad number '{}'", k))?,
+            None => 10,
+        }),
+        "sum" => Op::Sum,
+        "avg" => Op::Avg,
+        "count" => Op::Count,
+        _ => return Err(format!("unknown operation '{}'", name)),
+    };
+    let condition = args.option("where").map(str::parse).transpose()?;
+    let group_by = args.option("group-by");
+    if group_by.is_some() && !matches!(op, Op::Sum | Op::Avg | Op::Count) {
+        return Err(format!("--group-by does not work with '{}'", name));
+    }
+    let bad_rows = match args.flag("skip-bad-rows") {
+        true => BadRows::Skip,
+        false => BadRows::Fail,
+    };
+    Ok(Plan {
+        op,
+        name,
+        expr,
+        condition,
+        group_by,
+        bad_rows,
+    })
+}
+
+impl Plan<'_> {
+    fn fold<Row>(&self) -> Fold<Row> {
+        match self.op {
+            Op::Min => Fold::extreme(Ordering::Less),
+            Op::Max => Fold::extreme(Ordering::Greater),
+            Op::Top(k) => Fold::top(k),
+            Op::Sum => Fold::Sum(0.0),
+            Op::Avg => Fold::Avg(0.0, 0),
+            Op::Count => Fold::Count(0),
+        }
+    }
+
+    // A plain column is in the row already, so the value column is only added
+    // for the expressions
+    fn rows_output(&self, columns: &[Column], rows: &[(&Record, f64)]) -> Output {
+        let mut columns: Vec<String> = columns.iter().map(|c| c.name.clone()).collect();
+        let with_value = !matches!(self.expr, Expr::Column(_));
+        let rows = rows
+            .iter()
+            .map(|(row, value)| {
+                let mut cells: Vec<_> = columns
+                    .iter()
+                    .map(|c| row.text(c).map(String::from))
+                    .collect();
+                if with_value {
+                    cells.push(Some(number_text(*value)));
+                }
+                cells
+            })
+            .collect();
+        if with_value {
+            columns.push(self.expr.to_string());
+        }
+        Output { columns, rows }
+    }
+
+    fn aggregate_output(&self, groups: Vec<(String, f64)>) -> Output {
+        let name = match self.op {
+            Op::Count => "count".to_string(),
+            _ => format!("{}({})", self.name, self.expr),
+        };
+        match self.group_by {
+            Some(group_by) => Output {
+                columns: vec![group_by.to_string(), name],
+                rows: groups
+                    .into_iter()
+                    .map(|(key, value)| vec![Some(key), Some(number_text(value))])
+                    .collect(),
+            },
+            None => Output {
+                columns: vec![name],
+                rows: groups
+                    .into_iter()
+                    .map(|x| vec![Some(number_text(x.1))])
+                    .collect(),
+            },
+        }
+    }
+}
+
+// A file read at once goes through the query engine
+fn run_table(table: &Table, plan: &Plan) -> Result<Answer<Output>, MungeError> {
+    let mut query = Query::new(table).on_bad_rows(plan.bad_rows);
+    if let Some(condition) = &plan.condition {
+        query = query.filter(|row| condition.matches(row));
+    }
+    let expr = &plan.expr;
+
+    // Matching rows are printed with the value of the expression
+    let with_values = |answer: Answer<Vec<&Record>>| -> Result<Answer<Output>, MungeError> {
+        let rows: Result<Vec<_>, _> = answer
+            .value
+            .iter()
+            .map(|&row| Ok((row, expr.eval(row)?)))
+            .collect();
+        let output = plan.rows_output(table.columns(), &rows?);
+        Ok(answer.map(|_| output))
+    };
+    let aggregate = |answer: Answer<Vec<(String, f64)>>| answer.map(|x| plan.aggregate_output(x));
+    let total = |answer: Answer<f64>| aggregate(answer.map(|x| vec![(String::new(), x)]));
+
+    Ok(match (plan.op, plan.group_by) {
+        (Op::Min, _) => with_values(query.argmin(expr)?)?,
+        (Op::Max, _) => with_values(query.argmax(expr)?)?,
+        (Op::Top(k), _) => query
+            .top_k(expr, k)?
+            .map(|x| plan.rows_output(table.columns(), &x)),
+        (Op::Sum, None) => total(query.sum(expr)?),
+        (Op::Avg, None) => {
+            let answer = query.avg(expr)?;
+            aggregate(answer.map(|x| x.map(|x| (String::new(), x)).into_iter().collect()))
+        }
+        (Op::Count, None) => total(Answer {
+            value: query.count() as f64,
+            skipped: vec![],
+        }),
+        (Op::Sum, Some(column)) => aggregate(query.group_by(column)?.sum(expr)?),
+        (Op::Avg, Some(column)) => aggregate(query.group_by(column)?.avg(expr)?),
+        (Op::Count, Some(column)) => aggregate(query.group_by(column)?.count()?),
+    })
+}
+
+// Fold of the rows of a group and the rows skipped in it
+struct Group {
+    key: String,
+    fold: Fold<Record>,
+    skipped: Vec<MungeError>,
+    error: Option<MungeError>,
+}
+
+// The rows are read one by one and only the ones of the answer are kept. The
+// errors are reported as by the query engine: a broken input first, then a
+// bad group key, then a bad value of the first group having one.
+fn run_stream<R: BufRead>(mut rows: Rows<R>, plan: &Plan) -> Result<Answer<Output>, MungeError> {
+    let mut groups: Vec<Group> = Vec::new();
+    let mut skipped = Vec::new();
+    let mut key_error = None;
+    for row in rows.by_ref() {
+        let row = row?;
+        if plan.condition.as_ref().is_some_and(|x| !x.matches(&row)) {
+            continue;
+        }
+        let key = match plan.group_by.map(|column| row.value::<String>(column)) {
+            None => String::new(),
+            Some(Ok(key)) => key,
+            Some(Err(error)) => {
+                match plan.bad_rows {
+                    BadRows::Skip => skipped.push(error),
+                    BadRows::Fail => _ = key_error.get_or_insert(error),
+                }
+                continue;
+            }
+        };
+        let index = match groups.iter().position(|x| x.key == key) {
+            Some(index) => index,
+            None => {
+                groups.push(Group {
+                    key,
+                    fold: plan.fold(),
+                    skipped: Vec::new(),
+                    error: None,
+                });
+                groups.len() - 1
+            }
+        };
+        let group = &mut groups[index];
+        let value = match plan.op {
+            Op::Count => Ok(1.0),
+            _ => plan.expr.eval(&row),
+        };
+        match (value, plan.bad_rows) {
+            (Ok(value), _) => group.fold.push(row, value),
+            (Err(error), BadRows::Skip) => group.skipped.push(error),
+            (Err(error), BadRows::Fail) => _ = group.error.get_or_insert(error),
+        }
+    }
+    let mut errors = key_error
+        .into_iter()
+        .chain(groups.iter_mut().filter_map(|x| x.error.take()));
+    if let Some(error) = errors.next() {
+        return Err(error);
+    }
+    skipped.extend(
+        groups
+            .iter_mut()
+            .flat_map(|x| std::mem::take(&mut x.skipped)),
+    );
+
+    let columns = rows.header().map_or(&[][..], |x| x.columns());
+    let output = match (plan.op, plan.group_by) {
+        (Op::Min | Op::Max | Op::Top(_), _) => {
+            let best = groups.pop().map_or(Vec::new(), |x| x.fold.into_rows());
+            let best: Vec<_> = best.iter().map(|(row, value)| (row, *value)).collect();
+            plan.rows_output(columns, &best)
+        }
+        // Without rows, the sum and the count are still 0
+        (_, None) => {
+            let fold = groups.pop().map_or(plan.fold::<Record>(), |x| x.fold);
+            let total = fold.total().map(|x| (String::new(), x));
+            plan.aggregate_output(total.into_iter().collect())
+        }
+        // A group where every row was skipped has no average
+        (_, Some(_)) => plan.aggregate_output(
+            groups
+                .iter()
+                .map(|x| (x.key.clone(), x.fold.total().unwrap_or(f64::NAN)))
+                .collect(),
+        ),
+    };
+    Ok(Answer {
+        value: output,
+        skipped,
+    })
+}
+
+//////////////////////////////////////////////////////////////////////////////
+
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+fn json_string(text: &str) -> String {
+    let mut result = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            c if (c as u32) < 0x20 => result.push_str(&format!("\\u{:04x}", c as u32)),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+// JSON number: -?(0|[1-9][0-9]*)(.[0-9]+)?([eE][+-]?[0-9]+)?
+fn is_json_number(text: &str) -> bool {
+    let digits =
+        |text: &str| text.len() - text.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let rest = text.strip_prefix('-').unwrap_or(text);
+    let rest = match digits(rest) {
+        0 => return false,
+        1 => &rest[1..],
+        _ if rest.starts_with('0') => return false,
+        count => &rest[count..],
+    };
+    let rest = match rest.strip_prefix('.') {
+        Some(fraction) if digits(fraction) > 0 => &fraction[digits(fraction)..],
+        Some(_) => return false,
+        None => rest,
+    };
+    match rest.strip_prefix(['e', 'E']) {
+        Some(exponent) => {
+            let exponent = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+            digits(exponent) > 0 && digits(exponent) == exponent.len()
+        }
+        None => rest.is_empty(),
+    }
+}
+
+// Numbers are printed as is when JSON can read them, "050" or "97*" stay strings
+fn json_value(text: Option<&str>) -> String {
+    match text {
+        None => "null".to_string(),
+        Some(text) if is_json_number(text) => text.to_string(),
+        Some(text) => json_string(text),
+    }
+}
+
+fn print_output(output: &Output, format: Format, out: &mut dyn Write) -> std::io::Result<()> {
+    match format {
+        Format::Text => {
+            let mut widths: Vec<usize> = output.columns.iter().map(|c| c.chars().count()).collect();
+            for row in &output.rows {
+                for (width, cell) in widths.iter_mut().zip(row) {
+                    *width = (*width).max(cell.as_deref().map_or(0, |x| x.chars().count()));
+                }
+            }
+            let line = |cells: Vec<&str>| {
+                let cells: Vec<_> = cells
+                    .iter()
+                    .zip(&widths)
+                    .map(|(x, w)| format!("{:w$}", x, w = w))
+                    .collect();
+                cells.join("  ").trim_end().to_string()
+            };
+            writeln!(
+                out,
+                "{}",
+                line(output.columns.iter().map(String::as_str).collect())
+            )?;
+            for row in &output.rows {
+                writeln!(
+                    out,
+                    "{}",
+                    line(row.iter().map(|x| x.as_deref().unwrap_or("")).collect())
+                )?;
+            }
+        }
+        Format::Csv => {
+            let header: Vec<_> = output.columns.iter().map(|x| csv_field(x)).collect();
+            writeln!(out, "{}", header.join(","))?;
+            for row in &output.rows {
+                let cells: Vec<_> = row
+                    .iter()
+                    .map(|x| csv_field(x.as_deref().unwrap_or("")))
+                    .collect();
+                writeln!(out, "{}", cells.join(","))?;
+            }
+        }
+        Format::Json => {
+            for row in &output.rows {
+                let pairs: Vec<_> = output
+                    .columns
+                    .iter()
+                    .zip(row)
+                    .map(|(name, cell)| {
+                        format!("{}: {}", json_string(name), json_value(cell.as_deref()))
+                    })
+                    .collect();
+                writeln!(out, "{{{}}}", pairs.join(", "))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// Skipped rows go to 'err' as warnings, the result to 'out'
+pub fn munge(
+    args: &[String],
+    out: &mut dyn Write,
+    err: &mut dyn Write,
+) -> Result<ExitCode, String> {
+    let args = Args::parse(
+        args,
+        &[
+            "op", "columns", "expr", "where", "group-by", "k", "format", "input",
+        ],
+        &["skip-bad-rows"],
+    )?;
+    let [filename] = args.positional() else {
+        return Err(USAGE.to_string());
+    };
+    let format = parse_format(args.option("format"))?;
+    let input = match args.option("input") {
+        Some(name) => Some(
+            formats::Format::from_name(name).ok_or(format!("unknown input format '{}'", name))?,
+        ),
+        None => None,
+    };
+    let plan = parse_plan(&args)?;
+
+    // Plain files are read at once, the standard input, gzip files and the
+    // files with a format given are read row by row
+    let path = Path::new(filename);
+    let plain = filename != "-" && path.extension().is_none_or(|x| x != "gz");
+    let answer = match input {
+        None if plain => Table::from_file(path).and_then(|table| run_table(&table, &plan)),
+        _ => stream::open(path, input).and_then(|(reader, format)| {
+            run_stream(
+                stream::rows(path, reader, format).without_summaries(),
+                &plan,
+            )
+        }),
+    };
+    let answer = answer.map_err(|x| x.to_string())?;
+
+    let write_error = |x: std::io::Error| x.to_string();
+    for warning in &answer.skipped {
+        writeln!(err, "warning: skipped {}", warning).map_err(write_error)?;
+    }
+    print_output(&answer.value, format, out).map_err(write_error)?;
+    Ok(ExitCode::SUCCESS)
+}
+
+//////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+use crate::common::DATA_DIR;
+
+#[cfg(test)]
+fn run(file: &str, args: &[&str]) -> Result<(String, String), String> {
+    let mut all = vec![DATA_DIR.join(file).to_str().unwrap().to_string()];
+    all.extend(args.iter().map(|x| x.to_string()));
+    let mut out = Vec::new();
+    let mut err = Vec::new();
+    munge(&all, &mut out, &mut err)?;
+    let text = |x: Vec<u8>| {
+        String::from_utf8(x)
+            .unwrap()
+            .replace(DATA_DIR.to_str().unwrap(), "")
+    };
+    Ok((text(out), text(err)))
+}
+
+#[test]
+fn test_min_spread() {
+    let (out, err) = run(
+        "weather.dat",
+        &[
+            "--op",
+            "min-spread",
+            "--columns",
+            "MxT,MnT",
+            "--format",
+            "csv",
+        ],
+    )
+    .unwrap();
+    assert_eq!(
+        out,
+        "Dy,MxT,MnT,AvT,HDDay,AvDP,1HrP,TPcpn,WxType,PDir,AvSp,Dir,MxS,SkyC,MxR,MnR,AvSLP,abs(MxT - MnT)\n\
+         14,61,59,60,5,55.9,,0.00,RF,060,6.7,080,9,10.0,93,87,1008.6,2\n"
+    );
+    assert_eq!(err, "");
+
+    // The same answer whatever the input format
+    for file in [
+        "football.dat",
+        "football.csv",
+        "football.tsv",
+        "football.jsonl",
+    ] {
+        let (out, _) = run(
+            file,
+            &["--op", "min-spread", "--columns", "F,A", "--format", "json"],
+        )
+        .unwrap();
+        assert_eq!(
+            out,
+            "{\"Team\": \"Aston_Villa\", \"P\": 38, \"W\": 12, \"L\": 14, \"D\": 12, \"F\": 46, \"A\": 47, \"Pts\": 50, \"abs(F - A)\": 1}\n"
+        );
+    }
+}
+
+#[test]
+fn test_operations() {
+    let (out, _) = run("football.dat", &["--op", "max", "--columns", "A"]).unwrap();
+    assert_eq!(
+        out,
+        "Team       P   W  L   D   F   A   Pts\n\
+         Ipswich    38  9  9   20  41  64  36\n\
+         Leicester  38  5  13  20  30  64  28\n"
+    );
+
+    let (out, _) = run(
+        "football.csv",
+        &[
+            "--op", "top", "--k", "2", "--expr", "Pts / P", "--format", "csv",
+        ],
+    )
+    .unwrap();
+    assert_eq!(
+        out,
+        "Team,P,W,L,D,F,A,Pts,Pts / P\nArsenal,38,26,9,3,79,36,87,2.289473684210526\nLiverpool,38,24,8,6,67,30,80,2.1052631578947367\n"
+    );
+
+    let (out, _) = run("weather.dat", &["--op", "avg", "--expr", "MxT - MnT"]).unwrap();
+    assert_eq!(out, "avg(MxT - MnT)\n22.366666666666667\n");
+
+    let (out, _) = run(
+        "football.dat",
+        &["--op", "count", "--group-by", "Pts", "--format", "csv"],
+    )
+    .unwrap();
+    assert!(out.starts_with("Pts,count\n87,1\n80,1\n"));
+
+    let (out, err) = run(
+        "weather.dat",
+        &[
+            "--op",
+            "sum",
+            "--columns",
+            "HDDay",
+            "--group-by",
+            "WxType",
+            "--skip-bad-rows",
+        ],
+    )
+    .unwrap();
+    assert_eq!(out.lines().next(), Some("WxType  sum(HDDay)"));
+    assert!(out.contains("\nRF      5\n"));
+    assert_eq!(err.lines().count(), 28);
+    assert!(err.starts_with("warning: skipped /weather.dat:4: column 'WxType' is missing\n"));
+
+    // Only the rows matching the condition, the blank cells never match
+    let (out, _) = run("football.dat", &["--op", "count", "--where", "F - A > 10"]).unwrap();
+    assert_eq!(out, "count\n6\n");
+    let (out, err) = run(
+        "weather.dat",
+        &["--op", "sum", "--columns", "HDDay", "--where", "HDDay > 0"],
+    )
+    .unwrap();
+    assert_eq!((out.as_str(), err.as_str()), ("sum(HDDay)\n16\n", ""));
+}
+
+#[test]
+fn test_streamed_input() {
+    // The same answers, warnings and errors when read row by row
+    let cases: [(&str, &[&str]); 10] = [
+        (
+            "weather.dat",
+            &["--op", "min-spread", "--columns", "MxT,MnT"],
+        ),
+        (
+            "weather.dat",
+            &["--op", "top", "--k", "4", "--columns", "MxT"],
+        ),
+        ("weather.dat", &["--op", "avg", "--expr", "MxT - MnT"]),
+        (
+            "weather.dat",
+            &[
+                "--op",
+                "sum",
+                "--columns",
+                "HDDay",
+                "--group-by",
+                "WxType",
+                "--skip-bad-rows",
+            ],
+        ),
+        ("weather.dat", &["--op", "sum", "--columns", "HDDay"]),
+        (
+            "weather_bad.dat",
+            &["--op", "max", "--columns", "MxT", "--skip-bad-rows"],
+        ),
+        (
+            "weather_bad.dat",
+            &["--op", "avg", "--columns", "MnT", "--group-by", "WxType"],
+        ),
+        ("football.csv", &["--op", "count", "--group-by", "Pts"]),
+        (
+            "football.tsv",
+            &[
+                "--op",
+                "top",
+                "--k",
+                "3",
+                "--columns",
+                "F",
+                "--where",
+                "A < 40",
+            ],
+        ),
+        (
+            "football.jsonl",
+            &["--op", "max", "--columns", "A", "--format", "json"],
+        ),
+    ];
+    for (file, args) in cases {
+        let input = match file.rsplit_once('.') {
+            Some((_, "dat")) => "fixed",
+            Some((_, extension)) => extension,
+            None => unreachable!(),
+        };
+        let streamed = run(file, &[args, &["--input", input]].concat());
+        assert_eq!(run(file, args), streamed, "{} {:?}", file, args);
+    }
+}
+
+#[test]
+fn test_json_values() {
+    for number in ["0", "-7", "12", "0.5", "-1.25", "1e5", "2.5E-3", "10e+2"] {
+        assert_eq!(json_value(Some(number)), number);
+    }
+    for text in [
+        "5.", "5e", ".5", "050", "-", "+1", "1.e3", "1e+", "NaN", "inf", "97*",
+    ] {
+        assert_eq!(json_value(Some(text)), format!("\"{}\"", text));
+    }
+    assert_eq!(json_value(None), "null");
+}
+
+#[test]
+fn test_bad_command_lines() {
+    let error = |file, args| run(file, args).unwrap_err();
+    assert_eq!(
+        error("weather.dat", &["--columns", "MxT"]),
+        "--op is needed"
+    );
+    assert_eq!(
+        error("weather.dat", &["--op", "min"]),
+        "--columns or --expr is needed"
+    );
+    assert_eq!(
+        error("weather.dat", &["--op", "median", "--columns", "MxT"]),
+        "unknown operation 'median'"
+    );
+    assert_eq!(
+        error("weather.dat", &["--op", "min-spread", "--columns", "MxT"]),
+        "min-spread needs two columns, like --columns MxT,MnT"
+    );
+    assert_eq!(
+        error("weather.dat", &["--op", "min", "--expr", "MxT +"]),
+        "unexpected end of expression"
+    );
+    assert_eq!(
+        error("weather.dat", &["--op", "count", "--where", "MxT"]),
+        "no comparison in 'MxT'"
+    );
+    assert_eq!(
+        error(
+            "weather.dat",
+            &["--op", "min", "--columns", "MxT", "--format", "xml"]
+        ),
+        "unknown format 'xml'"
+    );
+    assert_eq!(
+        error("weather.dat", &["--op", "sum", "--columns", "HDDay"]),
+        format!(
+            "{}:3: column 'HDDay' is missing",
+            DATA_DIR.join("weather.dat").display()
+        )
+    );
+    assert!(error("no_such.dat", &["--op", "min", "--columns", "A"]).contains("no_such.dat: "));
+    assert_eq!(
+        munge(&[], &mut Vec::new(), &mut Vec::new()).unwrap_err(),
+        USAGE
+    );
+}
@@ -0,0 +1,98 @@
+use std::fmt;
+use std::path::PathBuf;
+
+// A cell that could not be read, empty text means the cell is missing
+#[derive(Debug, PartialEq)]
+pub struct BadCell {
+    pub column: String,
+    pub text: String,
+}
+
+impl BadCell {
+    pub fn new(column: &str, text: Option<&str>) -> BadCell {
+        BadCell {
+            column: column.to_string(),
+            text: text.unwrap_or_default().to_string(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum MungeError {
+    Io {
+        file: PathBuf,
+        error: std::io::Error,
+    },
+    BadRow {
+        file: PathBuf,
+        line: usize,
+        cell: BadCell,
+    },
+    Syntax {
+        file: PathBuf,
+        line: usize,
+        message: String,
+    },
+    #[cfg(test)]
+    NoRows { file: PathBuf },
+}
+
+impl fmt::Display for MungeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MungeError::Io { file, error } => write!(f, "{}: {}", file.display(), error),
+            MungeError::BadRow { file, line, cell } if cell.text.is_empty() => write!(
+                f,
+                "{}:{}: column '{}' is missing",
+                file.display(),
+                line,
+                cell.column
+            ),
+            MungeError::BadRow { file, line, cell } => write!(
+                f,
+                "{}:{}: column '{}' has bad value '{}'",
+                file.display(),
+                line,
+                cell.column,
+                cell.text
+            ),
+            MungeError::Syntax {
+                file,
+                line,
+                message,
+            } => write!(f, "{}:{}: {}", file.display(), line, message),
+            #[cfg(test)]
+            MungeError::NoRows { file } => write!(f, "{}: no data rows", file.display()),
+        }
+    }
+}
+
+impl std::error::Error for MungeError {}
+
+#[test]
+fn test_messages() {
+    let bad_value = MungeError::BadRow {
+        file: PathBuf::from("weather.dat"),
+        line: 7,
+        cell: BadCell::new("MxT", Some("9O")),
+    };
+    assert_eq!(
+        bad_value.to_string(),
+        "weather.dat:7: column 'MxT' has bad value '9O'"
+    );
+
+    let missing = MungeError::BadRow {
+        file: PathBuf::from("weather.dat"),
+        line: 10,
+        cell: BadCell::new("MnT", None),
+    };
+    assert_eq!(
+        missing.to_string(),
+        "weather.dat:10: column 'MnT' is missing"
+    );
+
+    let empty = MungeError::NoRows {
+        file: PathBuf::from("empty.dat"),
+    };
+    assert_eq!(empty.to_string(), "empty.dat: no data rows");
+}
@@ -0,0 +1,498 @@
+use super::error::MungeError;
+
+use std::iter::Peekable;
+use std::path::Path;
+use std::str::Chars;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Format {
+    Fixed,
+    Csv,
+    Tsv,
+    JsonLines,
+}
+
+impl Format {
+    // Everything but the known extensions is a '.dat' like fixed-width table
+    pub fn from_path(path: &Path) -> Format {
+        match path.extension().and_then(|x| x.to_str()) {
+            Some("csv") => Format::Csv,
+            Some("tsv") | Some("tab") => Format::Tsv,
+            Some("jsonl") | Some("ndjson") => Format::JsonLines,
+            _ => Format::Fixed,
+        }
+    }
+
+    // Name given on the command line, like '--input csv'
+    pub fn from_name(name: &str) -> Option<Format> {
+        match name {
+            "fixed" | "dat" => Some(Format::Fixed),
+            "csv" => Some(Format::Csv),
+            "tsv" => Some(Format::Tsv),
+            "jsonl" | "ndjson" => Some(Format::JsonLines),
+            _ => None,
+        }
+    }
+}
+
+// Column names and the rows with the line numbers they start at, empty cells are None
+pub struct RawTable {
+    pub names: Vec<String>,
+    pub rows: Vec<(usize, Vec<Option<String>>)>,
+}
+
+pub fn syntax_error(source: &Path, line: usize, message: &str) -> MungeError {
+    MungeError::Syntax {
+        file: source.to_path_buf(),
+        line,
+        message: message.to_string(),
+    }
+}
+
+pub fn non_empty(field: String) -> Option<String> {
+    Some(field).filter(|x| !x.is_empty())
+}
+
+// A row has a field for every column of the header, no more and no less
+pub fn check_width(
+    source: &Path,
+    line: usize,
+    fields: &[String],
+    width: usize,
+) -> Result<(), MungeError> {
+    match fields.len() == width {
+        true => Ok(()),
+        false => Err(syntax_error(
+            source,
+            line,
+            &format!("expected {} fields, found {}", width, fields.len()),
+        )),
+    }
+}
+
+// First record is the header, the rest are rows
+fn raw_table(
+    source: &Path,
+    mut records: Vec<(usize, Vec<String>)>,
+) -> Result<RawTable, MungeError> {
+    if records.is_empty() {
+        return Ok(RawTable {
+            names: vec![],
+            rows: vec![],
+        });
+    }
+    let names = records.remove(0).1;
+    let mut rows = Vec::new();
+    for (line, fields) in records {
+        check_width(source, line, &fields, names.len())?;
+        rows.push((line, fields.into_iter().map(non_empty).collect()));
+    }
+    Ok(RawTable { names, rows })
+}
+
+//////////////////////////////////////////////////////////////////////////////
+// CSV as in RFC 4180: quoted fields may contain delimiters, "" and line breaks
+
+pub fn read_csv(source: &Path, content: &str) -> Result<RawTable, MungeError> {
+    raw_table(source, split_csv(source, content, 1)?)
+}
+
+// Records with the numbers of the lines they start at, 'content' starts at 'first_line'
+pub fn split_csv(
+    source: &Path,
+    content: &str,
+    first_line: usize,
+) -> Result<Vec<(usize, Vec<String>)>, MungeError> {
+    let mut records = Vec::new();
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut line = first_line;
+    let mut record_line = first_line;
+    let mut in_quotes = false;
+    let mut quoted = false;
+
+    let mut chars = content.chars().peekable();
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => in_quotes = false,
+                _ => {
+                    line += usize::from(c == '\n');
+                    field.push(c);
+                }
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() && !quoted => {
+                in_quotes = true;
+                quoted = true;
+            }
+            ',' => {
+                fields.push(std::mem::take(&mut field));
+                quoted = false;
+            }
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                fields.push(std::mem::take(&mut field));
+                // A blank line is not a record with one empty field
+                if fields.len() > 1 || !fields[0].is_empty() || quoted {
+                    records.push((record_line, std::mem::take(&mut fields)));
+                }
+                fields.clear();
+                quoted = false;
+                line += 1;
+                record_line = line;
+            }
+            _ if quoted => return Err(syntax_error(source, line, "text after closing quote")),
+            '"' => return Err(syntax_error(source, line, "quote inside unquoted field")),
+            _ => field.push(c),
+        }
+    }
+    if in_quotes {
+        return Err(syntax_error(source, record_line, "unterminated quote"));
+    }
+    if !fields.is_empty() || !field.is_empty() || quoted {
+        fields.push(field);
+        records.push((record_line, fields));
+    }
+    Ok(records)
+}
+
+// A record is complete when its quotes are balanced, "" inside counts twice
+pub fn csv_record_complete(text: &str) -> bool {
+    text.matches('"').count().is_multiple_of(2)
+}
+
+//////////////////////////////////////////////////////////////////////////////
+// TSV has no quoting, a field just can't contain a tab or a line break
+
+pub fn read_tsv(source: &Path, content: &str) -> Result<RawTable, MungeError> {
+    let records = content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(index, line)| (index + 1, split_tsv(line)))
+        .collect();
+    raw_table(source, records)
+}
+
+pub fn split_tsv(line: &str) -> Vec<String> {
+    line.split('\t').map(String::from).collect()
+}
+
+//////////////////////////////////////////////////////////////////////////////
+// JSON lines: one flat object per line, the columns are all the keys in order
+// of appearance. Numbers keep their text, so "0.00" stays "0.00".
+
+struct JsonLine<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+impl JsonLine<'_> {
+    fn skip_spaces(&mut self) {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        self.skip_spaces();
+        match self.chars.next() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => Err(format!("expected '{}', found '{}'", expected, c)),
+            None => Err(format!("expected '{}', found end of line", expected)),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut text = String::new();
+        loop {
+            match self.chars.next() {
+                Some('"') => return Ok(text),
+                Some('\\') => text.push(self.escape()?),
+                Some(c) => text.push(c),
+                None => return Err("unterminated string".to_string()),
+            }
+        }
+    }
+
+    fn escape(&mut self) -> Result<char, String> {
+        Ok(match self.chars.next() {
+            Some('n') => '\n',
+            Some('t') => '\t',
+            Some('r') => '\r',
+            Some('b') => '\u{8}',
+            Some('f') => '\u{c}',
+            Some('u') => self.unicode_escape()?,
+            Some(c @ ('"' | '\\' | '/')) => c,
+            Some(c) => return Err(format!("bad escape '\\{}'", c)),
+            None => return Err("unterminated string".to_string()),
+        })
+    }
+
+    fn hex_code(&mut self) -> Result<u32, String> {
+        let code: String = (0..4).filter_map(|_| self.chars.next()).collect();
+        match code.len() == 4 && code.chars().all(|c| c.is_ascii_hexdigit()) {
+            true => Ok(u32::from_str_radix(&code, 16).unwrap()),
+            false => Err(format!("bad escape '\\u{}'", code)),
+        }
+    }
+
+    // Characters beyond U+FFFF are written as two escapes, a surrogate pair
+    fn unicode_escape(&mut self) -> Result<char, String> {
+        let high = self.hex_code()?;
+        if !(0xd800..0xdc00).contains(&high) {
+            return char::from_u32(high).ok_or(format!("bad escape '\\u{:04x}'", high));
+        }
+        let low = match (self.chars.next(), self.chars.next()) {
+            (Some('\\'), Some('u')) => self.hex_code()?,
+            _ => return Err(format!("unpaired surrogate '\\u{:04x}'", high)),
+        };
+        if !(0xdc00..0xe000).contains(&low) {
+            return Err(format!("unpaired surrogate '\\u{:04x}'", high));
+        }
+        let code = 0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00);
+        Ok(char::from_u32(code).unwrap())
+    }
+
+    // null is a missing cell, other scalars are their text
+    fn value(&mut self) -> Result<Option<String>, String> {
+        self.skip_spaces();
+        match self.chars.peek() {
+            Some('"') => self.string().map(Some),
+            Some('{') | Some('[') => Err("nested values are not supported".to_string()),
+            _ => {
+                let mut word = String::new();
+                while let Some(c) = self
+                    .chars
+                    .next_if(|c| c.is_alphanumeric() || "+-.".contains(*c))
+                {
+                    word.push(c);
+                }
+                match word.as_str() {
+                    "null" => Ok(None),
+                    "true" | "false" => Ok(Some(word)),
+                    // Rust also reads 'inf' and 'NaN', JSON numbers start with a digit
+                    _ if word.parse::<f64>().is_ok()
+                        && word
+                            .trim_start_matches('-')
+                            .starts_with(|c: char| c.is_ascii_digit()) =>
+                    {
+                        Ok(Some(word))
+                    }
+                    _ => Err(format!("bad value '{}'", word)),
+                }
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<Vec<(String, Option<String>)>, String> {
+        let mut pairs = Vec::new();
+        self.expect('{')?;
+        self.skip_spaces();
+        if self.chars.next_if_eq(&'}').is_none() {
+            loop {
+                let key = self.string()?;
+                self.expect(':')?;
+                pairs.push((key, self.value()?));
+                self.skip_spaces();
+                match self.chars.next() {
+                    Some(',') => self.skip_spaces(),
+                    Some('}') => break,
+                    _ => return Err("expected ',' or '}'".to_string()),
+                }
+            }
+        }
+        self.skip_spaces();
+        match self.chars.next() {
+            None => Ok(pairs),
+            Some(c) => Err(format!("unexpected '{}' after the object", c)),
+        }
+    }
+}
+
+// Keys and values of the object on a line, null values are None
+pub fn json_object(
+    source: &Path,
+    line: &str,
+    line_number: usize,
+) -> Result<Vec<(String, Option<String>)>, MungeError> {
+    let mut parser = JsonLine {
+        chars: line.chars().peekable(),
+    };
+    let pairs = parser
+        .object()
+        .map_err(|message| syntax_error(source, line_number, &message))?;
+    Ok(pairs
+        .into_iter()
+        .map(|(key, value)| (key, value.and_then(non_empty)))
+        .collect())
+}
+
+pub fn read_json_lines(source: &Path, content: &str) -> Result<RawTable, MungeError> {
+    let mut names: Vec<String> = Vec::new();
+    let mut rows = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let pairs = json_object(source, line, index + 1)?;
+
+        let mut cells = vec![None; names.len()];
+        for (key, value) in pairs {
+            let column = match names.iter().position(|x| *x == key) {
+                Some(column) => column,
+                None => {
+                    names.push(key);
+                    cells.push(None);
+                    names.len() - 1
+                }
+            };
+            cells[column] = value;
+        }
+        rows.push((index + 1, cells));
+    }
+    // Rows seen before a new key appeared are shorter
+    for (_, cells) in &mut rows {
+        cells.resize(names.len(), None);
+    }
+    Ok(RawTable { names, rows })
+}
+
+//////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_format_from_path() {
+    assert_eq!(Format::from_path(Path::new("weather.dat")), Format::Fixed);
+    assert_eq!(Format::from_path(Path::new("weather")), Format::Fixed);
+    assert_eq!(Format::from_path(Path::new("a/weather.csv")), Format::Csv);
+    assert_eq!(Format::from_path(Path::new("weather.tsv")), Format::Tsv);
+    assert_eq!(
+        Format::from_path(Path::new("weather.jsonl")),
+        Format::JsonLines
+    );
+}
+
+#[test]
+fn test_csv() {
+    let source = Path::new("test.csv");
+    let content =
+        "Name,Note,Score\r\n\"Smith, J\",\"said \"\"hi\"\"\",7\n\nLee,\"two\nlines\",\nKim,,\"\"";
+    let table = read_csv(source, content).unwrap();
+    assert_eq!(table.names, ["Name", "Note", "Score"]);
+
+    let text = |x: &str| Some(x.to_string());
+    assert_eq!(
+        table.rows,
+        [
+            (2, vec![text("Smith, J"), text("said \"hi\""), text("7")]),
+            (4, vec![text("Lee"), text("two\nlines"), None]),
+            (6, vec![text("Kim"), None, None]),
+        ]
+    );
+
+    let error = |content| read_csv(source, content).err().unwrap().to_string();
+    assert_eq!(error("A\n\"open\n\n"), "test.csv:2: unterminated quote");
+    assert_eq!(
+        error("A\nab\"c\n"),
+        "test.csv:2: quote inside unquoted field"
+    );
+    assert_eq!(
+        error("A\n\"ab\"c\n"),
+        "test.csv:2: text after closing quote"
+    );
+    assert_eq!(
+        error("A,B\n1,2\n3\n"),
+        "test.csv:3: expected 2 fields, found 1"
+    );
+    assert_eq!(
+        error("A,B\n1,2,3\n"),
+        "test.csv:2: expected 2 fields, found 3"
+    );
+}
+
+#[test]
+fn test_tsv() {
+    let source = Path::new("test.tsv");
+    let table = read_tsv(source, "Name\tScore\n\nSmith, J\t7\nLee\t\n").unwrap();
+    assert_eq!(table.names, ["Name", "Score"]);
+    let text = |x: &str| Some(x.to_string());
+    assert_eq!(
+        table.rows,
+        [
+            (3, vec![text("Smith, J"), text("7")]),
+            (4, vec![text("Lee"), None])
+        ]
+    );
+
+    let error = read_tsv(source, "Name\tScore\nLee\n").err().unwrap();
+    assert_eq!(error.to_string(), "test.tsv:2: expected 2 fields, found 1");
+}
+
+#[test]
+fn test_json_lines() {
+    let source = Path::new("test.jsonl");
+    let content = r#"{"Name": "Smith \"J\"", "Score": 7, "Ok": true}
+
+{ "Score" : -0.50e1 , "Name":"Lée\n", "Late": null }
+{"Extra": "x"}
+{}
+"#;
+    let table = read_json_lines(source, content).unwrap();
+    assert_eq!(table.names, ["Name", "Score", "Ok", "Late", "Extra"]);
+
+    let text = |x: &str| Some(x.to_string());
+    assert_eq!(
+        table.rows,
+        [
+            (
+                1,
+                vec![text("Smith \"J\""), text("7"), text("true"), None, None]
+            ),
+            (3, vec![text("Lée\n"), text("-0.50e1"), None, None, None]),
+            (4, vec![None, None, None, None, text("x")]),
+            (5, vec![None, None, None, None, None]),
+        ]
+    );
+
+    let error = |content| read_json_lines(source, content).err().unwrap().to_string();
+    assert_eq!(
+        error("{\"A\": [1]}"),
+        "test.jsonl:1: nested values are not supported"
+    );
+    assert_eq!(error("{}\n{\"A\": 1"), "test.jsonl:2: expected ',' or '}'");
+    assert_eq!(error("{\"A\": nope}"), "test.jsonl:1: bad value 'nope'");
+    assert_eq!(
+        error("{\"A\": \"open}"),
+        "test.jsonl:1: unterminated string"
+    );
+    assert_eq!(error("[1, 2]"), "test.jsonl:1: expected '{', found '['");
+    assert_eq!(
+        error(r#"{"A": "\u12g4"}"#),
+        "test.jsonl:1: bad escape '\\u12g4'"
+    );
+    assert_eq!(
+        error(r#"{"A": "\ud83d"}"#),
+        "test.jsonl:1: unpaired surrogate '\\ud83d'"
+    );
+    assert_eq!(
+        error(r#"{"A": "\ud83d\u0041"}"#),
+        "test.jsonl:1: unpaired surrogate '\\ud83d'"
+    );
+    assert_eq!(
+        error(r#"{"A": "\ude00"}"#),
+        "test.jsonl:1: bad escape '\\ude00'"
+    );
+    assert_eq!(
+        error("{} {}"),
+        "test.jsonl:1: unexpected '{' after the object"
+    );
+
+    // Escapes, surrogate pairs too
+    let table = read_json_lines(source, r#"{"A": "\u00e9\ud83d\ude00\u0041"}"#).unwrap();
+    assert_eq!(table.rows[0].1, [Some("é😀A".to_string())]);
+}
@@ -0,0 +1,469 @@
+use super::error::MungeError;
+use super::formats::syntax_error;
+use super::table::{Record, Table};
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+// Points for a result, 3-1-0 since 1981, 2-1-0 before
+#[derive(Copy, Clone, Debug)]
+pub struct Scoring {
+    pub win: u32,
+    pub draw: u32,
+    pub loss: u32,
+}
+
+impl Default for Scoring {
+    fn default() -> Scoring {
+        Scoring {
+            win: 3,
+            draw: 1,
+            loss: 0,
+        }
+    }
+}
+
+// Header names of the league columns. Note that football.dat has the draws
+// under 'L' and the losses under 'D': Arsenal has 26 * 3 + 9 = 87 points.
+pub struct LeagueColumns<'a> {
+    pub team: &'a str,
+    pub played: &'a str,
+    pub won: &'a str,
+    pub drawn: &'a str,
+    pub lost: &'a str,
+    pub goals_for: &'a str,
+    pub goals_against: &'a str,
+    pub points: &'a str,
+}
+
+impl LeagueColumns<'_> {
+    pub const AS_LABELED: LeagueColumns<'static> = LeagueColumns {
+        team: "Team",
+        played: "P",
+        won: "W",
+        drawn: "D",
+        lost: "L",
+        goals_for: "F",
+        goals_against: "A",
+        points: "Pts",
+    };
+
+    pub const FOOTBALL_DAT: LeagueColumns<'static> = LeagueColumns {
+        drawn: "L",
+        lost: "D",
+        ..LeagueColumns::AS_LABELED
+    };
+}
+
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Standing {
+    pub team: String,
+    pub played: u32,
+    pub won: u32,
+    pub drawn: u32,
+    pub lost: u32,
+    pub goals_for: u32,
+    pub goals_against: u32,
+    pub points: u32,
+}
+
+impl Standing {
+    fn parse(row: &Record, columns: &LeagueColumns) -> Result<Standing, MungeError> {
+        Ok(Standing {
+            team: row.value(columns.team)?,
+            played: row.value(columns.played)?,
+            won: row.value(columns.won)?,
+            drawn: row.value(columns.drawn)?,
+            lost: row.value(columns.lost)?,
+            goals_for: row.value(columns.goals_for)?,
+            goals_against: row.value(columns.goals_against)?,
+            points: row.value(columns.points)?,
+        })
+    }
+
+    pub fn goal_difference(&self) -> i64 {
+        i64::from(self.goals_for) - i64::from(self.goals_against)
+    }
+
+    // None when the numbers of the file are too large to add up
+    fn results(&self) -> Option<u32> {
+        self.won.checked_add(self.drawn)?.checked_add(self.lost)
+    }
+
+    fn expected_points(&self, scoring: &Scoring) -> Option<u32> {
+        self.won
+            .checked_mul(scoring.win)?
+            .checked_add(self.drawn.checked_mul(scoring.draw)?)?
+            .checked_add(self.lost.checked_mul(scoring.loss)?)
+    }
+
+    fn rank_key(&self) -> (u32, i64) {
+        (self.points, self.goal_difference())
+    }
+
+    fn add_game(&mut self, scored: u32, conceded: u32, scoring: &Scoring) {
+        self.played += 1;
+        self.goals_for += scored;
+        self.goals_against += conceded;
+        let (count, points) = match scored.cmp(&conceded) {
+            std::cmp::Ordering::Greater => (&mut self.won, scoring.win),
+            std::cmp::Ordering::Equal => (&mut self.drawn, scoring.draw),
+            std::cmp::Ordering::Less => (&mut self.lost, scoring.loss),
+        };
+        *count += 1;
+        self.points += points;
+    }
+}
+
+// What does not add up in a row of the table
+#[derive(PartialEq, Debug)]
+pub enum Problem {
+    Games {
+        team: String,
+        played: u32,
+        results: u32,
+    },
+    Points {
+        team: String,
+        expected: u32,
+        found: u32,
+    },
+    Order {
+        team: String,
+        above: String,
+    },
+    Overflow {
+        team: String,
+    },
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::Games {
+                team,
+                played,
+                results,
+            } => write!(
+                f,
+                "{}: played {} games, but has {} results",
+                team, played, results
+            ),
+            Problem::Points {
+                team,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{}: has {} points, the results give {}",
+                team, found, expected
+            ),
+            Problem::Order { team, above } => {
+                write!(
+                    f,
+                    "{}: ranked below {}, but has more points or a better goal difference",
+                    team, above
+                )
+            }
+            Problem::Overflow { team } => {
+                write!(f, "{}: results or points too large to add up", team)
+            }
+        }
+    }
+}
+
+// One result line: "Arsenal 2-1 Chelsea", the home team first
+#[derive(PartialEq, Debug)]
+pub struct Match {
+    pub home: String,
+    pub away: String,
+    pub home_goals: u32,
+    pub away_goals: u32,
+}
+
+fn parse_match(line: &str) -> Result<Match, String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let [home, score, away] = words[..] else {
+        return Err(format!("expected 'Home 2-1 Away', found '{}'", line));
+    };
+    let goals = score
+        .split_once('-')
+        .and_then(|(a, b)| Some((a.parse().ok()?, b.parse().ok()?)));
+    let Some((home_goals, away_goals)) = goals else {
+        return Err(format!("bad score '{}'", score));
+    };
+    if home == away {
+        return Err(format!("{} can't play itself", home));
+    }
+    Ok(Match {
+        home: home.to_string(),
+        away: away.to_string(),
+        home_goals,
+        away_goals,
+    })
+}
+
+// Blank lines and the lines starting with '#' are skipped
+pub fn read_matches(source: &Path, content: &str) -> Result<Vec<Match>, MungeError> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .map(|(index, line)| {
+            parse_match(line).map_err(|message| syntax_error(source, index + 1, &message))
+        })
+        .collect()
+}
+
+// Standings in the order of the table
+#[derive(PartialEq, Debug)]
+pub struct League {
+    pub standings: Vec<Standing>,
+}
+
+impl League {
+    // Every data row is a standing, the table skips the '-----' separator line
+    pub fn from_table(table: &Table, columns: &LeagueColumns) -> Result<League, MungeError> {
+        let standings = table
+            .rows()
+            .iter()
+            .map(|row| Standing::parse(row, columns))
+            .collect::<Result<_, _>>()?;
+        Ok(League { standings })
+    }
+
+    // Ranked by points, then goal difference, then goals scored, then the name
+    pub fn from_results(matches: &[Match], scoring: &Scoring) -> League {
+        let mut standings: HashMap<&str, Standing> = HashMap::new();
+        for game in matches {
+            let sides = [
+                (&game.home, game.home_goals, game.away_goals),
+                (&game.away, game.away_goals, game.home_goals),
+            ];
+            for (team, scored, conceded) in sides {
+                let standing = standings.entry(team).or_insert_with(|| Standing {
+                    team: team.to_string(),
+                    ..Default::default()
+                });
+                standing.add_game(scored, conceded, scoring);
+            }
+        }
+        let mut standings: Vec<Standing> = standings.into_values().collect();
+        standings.sort_by(|a, b| {
+            let key = |x: &Standing| (Reverse(x.rank_key()), Reverse(x.goals_for));
+            key(a).cmp(&key(b)).then_with(|| a.team.cmp(&b.team))
+        });
+        League { standings }
+    }
+
+    // Every row on its own, then every row against the one above it
+    pub fn check(&self, scoring: &Scoring) -> Vec<Problem> {
+        let mut problems = Vec::new();
+        for row in &self.standings {
+            let (Some(results), Some(expected)) = (row.results(), row.expected_points(scoring))
+            else {
+                problems.push(Problem::Overflow {
+                    team: row.team.clone(),
+                });
+                continue;
+            };
+            if results != row.played {
+                problems.push(Problem::Games {
+                    team: row.team.clone(),
+                    played: row.played,
+                    results,
+                });
+            }
+            if expected != row.points {
+                problems.push(Problem::Points {
+                    team: row.team.clone(),
+                    expected,
+                    found: row.points,
+                });
+            }
+        }
+        for pair in self.standings.windows(2) {
+            if pair[1].rank_key() > pair[0].rank_key() {
+                problems.push(Problem::Order {
+                    team: pair[1].team.clone(),
+                    above: pair[0].team.clone(),
+                });
+            }
+        }
+        problems
+    }
+}
+
+// The same layout as football.dat, with the columns in their real order
+impl fmt::Display for League {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self
+            .standings
+            .iter()
+            .map(|x| x.team.len())
+            .max()
+            .unwrap_or(0)
+            .max(4);
+        writeln!(
+            f,
+            "     {:width$}   P   W   D   L   F   A   GD  Pts",
+            "Team",
+            width = width
+        )?;
+        for (index, row) in self.standings.iter().enumerate() {
+            writeln!(
+                f,
+                "{:>3}. {:width$} {:>3} {:>3} {:>3} {:>3} {:>3} {:>3} {:>+4} {:>4}",
+                index + 1,
+                row.team,
+                row.played,
+                row.won,
+                row.drawn,
+                row.lost,
+                row.goals_for,
+                row.goals_against,
+                row.goal_difference(),
+                row.points,
+                width = width
+            )?;
+        }
+        Ok(())
+    }
+}
+
+//////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+use crate::common::DATA_DIR;
+
+#[cfg(test)]
+fn football(name: &str, columns: &LeagueColumns) -> League {
+    let table = Table::from_file(&DATA_DIR.join(name)).unwrap();
+    League::from_table(&table, columns).unwrap()
+}
+
+#[test]
+fn test_football_checks() {
+    // Read as labeled, the points only add up when draws equal losses
+    for name in ["football.dat", "football.csv", "football.jsonl"] {
+        let problems = football(name, &LeagueColumns::AS_LABELED).check(&Scoring::default());
+        assert_eq!(problems.len(), 18, "{}", name);
+        assert!(problems.iter().all(|x| matches!(x, Problem::Points { .. })));
+        assert_eq!(
+            problems[0].to_string(),
+            "Arsenal: has 87 points, the results give 81"
+        );
+        assert!(!problems.iter().any(|x| x.to_string().starts_with("Fulham")));
+    }
+
+    let league = football("football.dat", &LeagueColumns::FOOTBALL_DAT);
+    assert_eq!(league.standings.len(), 20);
+    assert_eq!(league.check(&Scoring::default()), []);
+    assert_eq!(league.standings[19].team, "Leicester");
+    assert_eq!(
+        (league.standings[19].drawn, league.standings[19].lost),
+        (13, 20)
+    );
+
+    // Two points for a win, every team with a win is wrong then
+    let problems = league.check(&Scoring {
+        win: 2,
+        ..Scoring::default()
+    });
+    assert_eq!(problems.len(), 20);
+}
+
+#[test]
+fn test_broken_table() {
+    let mut league = football("football.dat", &LeagueColumns::FOOTBALL_DAT);
+    league.standings.swap(7, 8); // Tottenham has the same points, but a worse goal difference
+    league.standings[0].played = 37;
+    league.standings[2].points += 1;
+    league.standings[5].won = u32::MAX; // a malformed row
+    let problems: Vec<String> = league
+        .check(&Scoring::default())
+        .iter()
+        .map(|x| x.to_string())
+        .collect();
+    assert_eq!(
+        problems,
+        [
+            "Arsenal: played 37 games, but has 38 results",
+            "Manchester_U: has 78 points, the results give 77",
+            "Chelsea: results or points too large to add up",
+            "Aston_Villa: ranked below Tottenham, but has more points or a better goal difference",
+        ]
+    );
+}
+
+#[test]
+fn test_standings_from_results() {
+    let filename = DATA_DIR.join("matches.txt");
+    let matches = read_matches(&filename, &std::fs::read_to_string(&filename).unwrap()).unwrap();
+    assert_eq!(matches.len(), 6);
+    assert_eq!(
+        matches[0],
+        Match {
+            home: "Arsenal".to_string(),
+            away: "Chelsea".to_string(),
+            home_goals: 2,
+            away_goals: 1
+        }
+    );
+
+    let league = League::from_results(&matches, &Scoring::default());
+    assert_eq!(league.check(&Scoring::default()), []);
+    assert_eq!(
+        league.to_string(),
+        "     Team      P   W   D   L   F   A   GD  Pts\n  \
+         1. Chelsea   3   2   0   1   6   3   +3    6\n  \
+         2. Arsenal   3   1   2   0   5   4   +1    5\n  \
+         3. Leeds     3   0   2   1   2   3   -1    2\n  \
+         4. Fulham    3   0   2   1   2   5   -3    2\n"
+    );
+
+    // With two points for a win Arsenal catches up, goal difference decides
+    let league = League::from_results(
+        &matches,
+        &Scoring {
+            win: 2,
+            draw: 1,
+            loss: 0,
+        },
+    );
+    let teams: Vec<&str> = league.standings.iter().map(|x| x.team.as_str()).collect();
+    assert_eq!(teams, ["Chelsea", "Arsenal", "Leeds", "Fulham"]);
+    assert_eq!(
+        (league.standings[0].points, league.standings[1].points),
+        (4, 4)
+    );
+
+    // The rendered table reads back and checks as well
+    let rendered = League::from_results(&matches, &Scoring::default()).to_string();
+    let table = Table::parse(&rendered).unwrap();
+    let read = League::from_table(&table, &LeagueColumns::AS_LABELED).unwrap();
+    assert_eq!(
+        read.standings,
+        League::from_results(&matches, &Scoring::default()).standings
+    );
+}
+
+#[test]
+fn test_bad_results() {
+    let source = Path::new("matches.txt");
+    let error = |content| read_matches(source, content).unwrap_err().to_string();
+    assert_eq!(
+        error("A 1-0 B\n\nA 1:0 C"),
+        "matches.txt:3: bad score '1:0'"
+    );
+    assert_eq!(
+        error("A 1 - 0 B"),
+        "matches.txt:1: expected 'Home 2-1 Away', found 'A 1 - 0 B'"
+    );
+    assert_eq!(
+        error("# comment\nA 2-2 A"),
+        "matches.txt:2: A can't play itself"
+    );
+}
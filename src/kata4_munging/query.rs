@@ -0,0 +1,716 @@
+use super::error::MungeError;
+use super::table::{Record, Table};
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::iter::Peekable;
+use std::ops;
+use std::str::{Chars, FromStr};
+
+// What to do with a row which can't be evaluated
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum BadRows {
+    Fail,
+    Skip,
+}
+
+// The result and the rows skipped on the way
+#[derive(Debug)]
+pub struct Answer<T> {
+    pub value: T,
+    pub skipped: Vec<MungeError>,
+}
+
+impl<T> Answer<T> {
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Answer<U> {
+        Answer {
+            value: f(self.value),
+            skipped: self.skipped,
+        }
+    }
+}
+
+// Keep the good rows, a bad one either stops everything or goes to 'skipped'
+pub fn collect_rows<Row>(
+    rows: impl Iterator<Item = Result<Row, MungeError>>,
+    bad_rows: BadRows,
+) -> Result<Answer<Vec<Row>>, MungeError> {
+    let mut good = Vec::new();
+    let mut skipped = Vec::new();
+    for row in rows {
+        match row {
+            Ok(row) => good.push(row),
+            Err(error) if bad_rows == BadRows::Skip => skipped.push(error),
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(Answer {
+        value: good,
+        skipped,
+    })
+}
+
+//////////////////////////////////////////////////////////////////////////////
+// Arithmetic over the columns of a row: (col("MxT") - col("MnT")).abs()
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Expr {
+    Column(String),
+    Const(f64),
+    Neg(Box<Expr>),
+    Abs(Box<Expr>),
+    Add(Box<Expr>, Box<Expr>),
+    Sub(Box<Expr>, Box<Expr>),
+    Mul(Box<Expr>, Box<Expr>),
+    Div(Box<Expr>, Box<Expr>),
+}
+
+pub fn col(name: &str) -> Expr {
+    Expr::Column(name.to_string())
+}
+
+impl Expr {
+    pub fn abs(self) -> Expr {
+        Expr::Abs(Box::new(self))
+    }
+
+    pub fn eval(&self, row: &Record) -> Result<f64, MungeError> {
+        Ok(match self {
+            Expr::Column(name) => row.value(name)?,
+            Expr::Const(value) => *value,
+            Expr::Neg(x) => -x.eval(row)?,
+            Expr::Abs(x) => x.eval(row)?.abs(),
+            Expr::Add(a, b) => a.eval(row)? + b.eval(row)?,
+            Expr::Sub(a, b) => a.eval(row)? - b.eval(row)?,
+            Expr::Mul(a, b) => a.eval(row)? * b.eval(row)?,
+            Expr::Div(a, b) => a.eval(row)? / b.eval(row)?,
+        })
+    }
+}
+
+macro_rules! expr_operator {
+    ($trait:ident, $method:ident, $variant:ident) => {
+        impl ops::$trait for Expr {
+            type Output = Expr;
+
+            fn $method(self, rhs: Expr) -> Expr {
+                Expr::$variant(Box::new(self), Box::new(rhs))
+            }
+        }
+
+        impl ops::$trait<f64> for Expr {
+            type Output = Expr;
+
+            fn $method(self, rhs: f64) -> Expr {
+                Expr::$variant(Box::new(self), Box::new(Expr::Const(rhs)))
+            }
+        }
+    };
+}
+
+expr_operator!(Add, add, Add);
+expr_operator!(Sub, sub, Sub);
+expr_operator!(Mul, mul, Mul);
+expr_operator!(Div, div, Div);
+
+impl ops::Neg for Expr {
+    type Output = Expr;
+
+    fn neg(self) -> Expr {
+        Expr::Neg(Box::new(self))
+    }
+}
+
+// Text form for the command line: "abs(MxT - MnT)", "F - A", "AvT * 2"
+impl FromStr for Expr {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Expr, String> {
+        let mut parser = ExprParser {
+            chars: text.chars().peekable(),
+        };
+        let expr = parser.sum()?;
+        match parser.token() {
+            None => Ok(expr),
+            Some(token) => Err(format!("unexpected '{}' in '{}'", token, text)),
+        }
+    }
+}
+
+struct ExprParser<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+impl ExprParser<'_> {
+    // A word (column name, number or function) or a single sign
+    fn token(&mut self) -> Option<String> {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let is_word = |c: &char| c.is_alphanumeric() || *c == '_' || *c == '.';
+        let first = self.chars.next()?;
+        let mut token = first.to_string();
+        if is_word(&first) {
+            while let Some(c) = self.chars.next_if(is_word) {
+                token.push(c);
+            }
+        }
+        Some(token)
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+        self.chars.peek().copied()
+    }
+
+    fn sum(&mut self) -> Result<Expr, String> {
+        let mut expr = self.product()?;
+        while let Some(sign @ ('+' | '-')) = self.peek() {
+            self.chars.next();
+            let rhs = self.product()?;
+            expr = if sign == '+' { expr + rhs } else { expr - rhs };
+        }
+        Ok(expr)
+    }
+
+    fn product(&mut self) -> Result<Expr, String> {
+        let mut expr = self.unary()?;
+        while let Some(sign @ ('*' | '/')) = self.peek() {
+            self.chars.next();
+            let rhs = self.unary()?;
+            expr = if sign == '*' { expr * rhs } else { expr / rhs };
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        match self.token().as_deref() {
+            Some("-") => Ok(-self.unary()?),
+            Some("(") => self.closing(),
+            Some("abs") if self.peek() == Some('(') => {
+                self.chars.next();
+                Ok(self.closing()?.abs())
+            }
+            // Column names may start with a digit, like '1HrP'
+            Some(word) if word.starts_with(|c: char| c.is_alphanumeric() || c == '_') => {
+                Ok(match word.parse() {
+                    Ok(value) => Expr::Const(value),
+                    Err(_) => col(word),
+                })
+            }
+            Some(token) => Err(format!("unexpected '{}'", token)),
+            None => Err("unexpected end of expression".to_string()),
+        }
+    }
+
+    fn closing(&mut self) -> Result<Expr, String> {
+        let expr = self.sum()?;
+        match self.token().as_deref() {
+            Some(")") => Ok(expr),
+            _ => Err("missing ')'".to_string()),
+        }
+    }
+}
+
+impl Expr {
+    // Binding strength, the parentheses are printed only where they are needed
+    fn precedence(&self) -> u8 {
+        match self {
+            Expr::Add(..) | Expr::Sub(..) => 1,
+            Expr::Mul(..) | Expr::Div(..) => 2,
+            Expr::Neg(_) => 3,
+            _ => 4,
+        }
+    }
+
+    fn fmt_operand(&self, f: &mut fmt::Formatter<'_>, precedence: u8) -> fmt::Result {
+        if self.precedence() < precedence {
+            write!(f, "({})", self)
+        } else {
+            write!(f, "{}", self)
+        }
+    }
+
+    fn fmt_binary(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        a: &Expr,
+        sign: &str,
+        b: &Expr,
+    ) -> fmt::Result {
+        a.fmt_operand(f, self.precedence())?;
+        write!(f, " {} ", sign)?;
+        b.fmt_operand(f, self.precedence() + 1)
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Column(name) => write!(f, "{}", name),
+            Expr::Const(value) => write!(f, "{}", value),
+            Expr::Neg(x) => {
+                write!(f, "-")?;
+                x.fmt_operand(f, self.precedence())
+            }
+            Expr::Abs(x) => write!(f, "abs({})", x),
+            Expr::Add(a, b) => self.fmt_binary(f, a, "+", b),
+            Expr::Sub(a, b) => self.fmt_binary(f, a, "-", b),
+            Expr::Mul(a, b) => self.fmt_binary(f, a, "*", b),
+            Expr::Div(a, b) => self.fmt_binary(f, a, "/", b),
+        }
+    }
+}
+
+//////////////////////////////////////////////////////////////////////////////
+// Comparison of two expressions to pick rows: "Pts >= 60", "MxT - MnT < 10"
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+enum Comparison {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Equal,
+    NotEqual,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Condition {
+    left: Expr,
+    comparison: Comparison,
+    right: Expr,
+}
+
+impl Condition {
+    // A row where either side can't be evaluated doesn't match
+    pub fn matches(&self, row: &Record) -> bool {
+        let (Ok(a), Ok(b)) = (self.left.eval(row), self.right.eval(row)) else {
+            return false;
+        };
+        match self.comparison {
+            Comparison::Less => a < b,
+            Comparison::LessOrEqual => a <= b,
+            Comparison::Greater => a > b,
+            Comparison::GreaterOrEqual => a >= b,
+            Comparison::Equal => a == b,
+            Comparison::NotEqual => a != b,
+        }
+    }
+}
+
+impl FromStr for Condition {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Condition, String> {
+        let at = text
+            .find(['<', '>', '=', '!'])
+            .ok_or(format!("no comparison in '{}'", text))?;
+        let (left, rest) = text.split_at(at);
+        let signs = [
+            ("<=", Comparison::LessOrEqual),
+            (">=", Comparison::GreaterOrEqual),
+            ("==", Comparison::Equal),
+            ("!=", Comparison::NotEqual),
+            ("<", Comparison::Less),
+            (">", Comparison::Greater),
+            ("=", Comparison::Equal),
+        ];
+        let (comparison, right) = signs
+            .into_iter()
+            .find_map(|(sign, comparison)| rest.strip_prefix(sign).map(|x| (comparison, x)))
+            .ok_or(format!("unexpected '!' in '{}'", text))?;
+        Ok(Condition {
+            left: left.parse()?,
+            comparison,
+            right: right.parse()?,
+        })
+    }
+}
+
+//////////////////////////////////////////////////////////////////////////////
+// Operations fed with one row at a time, so that they work on a stream as
+// well as on a table. Only the rows of the answer are kept.
+
+pub enum Fold<Row> {
+    // All the rows sharing the extreme value, no silent pick of the first one
+    Extreme(Ordering, Vec<(Row, f64)>),
+    // Largest first, the rows with equal values keep the input order
+    Top(usize, Vec<(Row, f64)>),
+    Sum(f64),
+    Avg(f64, usize),
+    Count(usize),
+}
+
+impl<Row> Fold<Row> {
+    pub fn extreme(wanted: Ordering) -> Fold<Row> {
+        Fold::Extreme(wanted, Vec::new())
+    }
+
+    pub fn top(k: usize) -> Fold<Row> {
+        Fold::Top(k, Vec::new())
+    }
+
+    pub fn push(&mut self, row: Row, value: f64) {
+        match self {
+            Fold::Extreme(wanted, best) => match best.first().map(|x| value.total_cmp(&x.1)) {
+                None | Some(Ordering::Equal) => best.push((row, value)),
+                Some(order) if order == *wanted => *best = vec![(row, value)],
+                _ => {}
+            },
+            Fold::Top(k, best) => {
+                let at = best.partition_point(|x| x.1.total_cmp(&value) != Ordering::Less);
+                if at < *k {
+                    best.insert(at, (row, value));
+                    best.truncate(*k);
+                }
+            }
+            Fold::Sum(sum) => *sum += value,
+            Fold::Avg(sum, count) => {
+                *sum += value;
+                *count += 1;
+            }
+            Fold::Count(count) => *count += 1,
+        }
+    }
+
+    // Rows of argmin, argmax and top
+    pub fn into_rows(self) -> Vec<(Row, f64)> {
+        match self {
+            Fold::Extreme(_, rows) | Fold::Top(_, rows) => rows,
+            _ => Vec::new(),
+        }
+    }
+
+    // Value of an aggregate, None for the average of no rows
+    pub fn total(&self) -> Option<f64> {
+        match *self {
+            Fold::Sum(sum) => Some(sum),
+            Fold::Avg(sum, count) => Some(sum / count as f64).filter(|_| count > 0),
+            Fold::Count(count) => Some(count as f64),
+            _ => None,
+        }
+    }
+}
+
+//////////////////////////////////////////////////////////////////////////////
+
+pub struct Query<'a> {
+    rows: Vec<&'a Record>,
+    bad_rows: BadRows,
+}
+
+// Rows grouped by the text of a column, in order of the first appearance
+pub struct Groups<'a> {
+    groups: Vec<(String, Vec<&'a Record>)>,
+    bad_rows: BadRows,
+    skipped: Vec<MungeError>,
+}
+
+impl<'a> Query<'a> {
+    // Summary rows are not a part of the data
+    pub fn new(table: &'a Table) -> Query<'a> {
+        Query {
+            rows: table.rows().iter().collect(),
+            bad_rows: BadRows::Fail,
+        }
+    }
+
+    pub fn on_bad_rows(mut self, bad_rows: BadRows) -> Query<'a> {
+        self.bad_rows = bad_rows;
+        self
+    }
+
+    pub fn filter(mut self, pred: impl Fn(&Record) -> bool) -> Query<'a> {
+        self.rows.retain(|row| pred(row));
+        self
+    }
+
+    fn values(&self, expr: &Expr) -> Result<Answer<Vec<(&'a Record, f64)>>, MungeError> {
+        let rows = self.rows.iter().map(|&row| Ok((row, expr.eval(row)?)));
+        collect_rows(rows, self.bad_rows)
+    }
+
+    // The values of the rows one by one into the fold
+    fn fold(
+        &self,
+        expr: &Expr,
+        mut fold: Fold<&'a Record>,
+    ) -> Result<Answer<Fold<&'a Record>>, MungeError> {
+        let answer = self.values(expr)?;
+        for (row, value) in answer.value.iter().copied() {
+            fold.push(row, value);
+        }
+        Ok(Answer {
+            value: fold,
+            skipped: answer.skipped,
+        })
+    }
+
+    fn extreme(
+        &self,
+        expr: &Expr,
+        wanted: Ordering,
+    ) -> Result<Answer<Vec<&'a Record>>, MungeError> {
+        let answer = self.fold(expr, Fold::extreme(wanted))?;
+        Ok(answer.map(|x| x.into_rows().into_iter().map(|x| x.0).collect()))
+    }
+
+    pub fn argmin(&self, expr: &Expr) -> Result<Answer<Vec<&'a Record>>, MungeError> {
+        self.extreme(expr, Ordering::Less)
+    }
+
+    pub fn argmax(&self, expr: &Expr) -> Result<Answer<Vec<&'a Record>>, MungeError> {
+        self.extreme(expr, Ordering::Greater)
+    }
+
+    pub fn top_k(
+        &self,
+        expr: &Expr,
+        k: usize,
+    ) -> Result<Answer<Vec<(&'a Record, f64)>>, MungeError> {
+        Ok(self.fold(expr, Fold::top(k))?.map(Fold::into_rows))
+    }
+
+    pub fn sum(&self, expr: &Expr) -> Result<Answer<f64>, MungeError> {
+        Ok(self.fold(expr, Fold::Sum(0.0))?.map(|x| x.total().unwrap()))
+    }
+
+    // None for no rows at all
+    pub fn avg(&self, expr: &Expr) -> Result<Answer<Option<f64>>, MungeError> {
+        Ok(self.fold(expr, Fold::Avg(0.0, 0))?.map(|x| x.total()))
+    }
+
+    pub fn count(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn group_by(&self, column: &str) -> Result<Groups<'a>, MungeError> {
+        let rows = self
+            .rows
+            .iter()
+            .map(|&row| Ok((row.value::<String>(column)?, row)));
+        let answer = collect_rows(rows, self.bad_rows)?;
+
+        let mut groups: Vec<(String, Vec<&Record>)> = Vec::new();
+        for (key, row) in answer.value {
+            match groups.iter_mut().find(|x| x.0 == key) {
+                Some(group) => group.1.push(row),
+                None => groups.push((key, vec![row])),
+            }
+        }
+        Ok(Groups {
+            groups,
+            bad_rows: self.bad_rows,
+            skipped: answer.skipped,
+        })
+    }
+}
+
+impl Groups<'_> {
+    fn aggregate(
+        self,
+        aggregate: impl Fn(&Query) -> Result<Answer<f64>, MungeError>,
+    ) -> Result<Answer<Vec<(String, f64)>>, MungeError> {
+        let mut skipped = self.skipped;
+        let mut value = Vec::new();
+        for (key, rows) in self.groups {
+            let query = Query {
+                rows,
+                bad_rows: self.bad_rows,
+            };
+            let answer = aggregate(&query)?;
+            skipped.extend(answer.skipped);
+            value.push((key, answer.value));
+        }
+        Ok(Answer { value, skipped })
+    }
+
+    pub fn sum(self, expr: &Expr) -> Result<Answer<Vec<(String, f64)>>, MungeError> {
+        self.aggregate(|query| query.sum(expr))
+    }
+
+    // A group where every row was skipped has no average
+    pub fn avg(self, expr: &Expr) -> Result<Answer<Vec<(String, f64)>>, MungeError> {
+        self.aggregate(|query| Ok(query.avg(expr)?.map(|x| x.unwrap_or(f64::NAN))))
+    }
+
+    pub fn count(self) -> Result<Answer<Vec<(String, f64)>>, MungeError> {
+        self.aggregate(|query| {
+            Ok(Answer {
+                value: query.count() as f64,
+                skipped: vec![],
+            })
+        })
+    }
+}
+
+//////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+use crate::common::DATA_DIR;
+
+#[cfg(test)]
+fn texts(rows: &[&Record], column: &str) -> Vec<String> {
+    rows.iter()
+        .map(|row| row.text(column).unwrap().to_string())
+        .collect()
+}
+
+#[test]
+fn test_expressions() {
+    let table = Table::parse("A   B   C\n3   5   x\n").unwrap();
+    let row = &table.rows()[0];
+    assert_eq!(col("A").eval(row).unwrap(), 3.0);
+    assert_eq!((col("A") - col("B")).eval(row).unwrap(), -2.0);
+    assert_eq!((col("A") - col("B")).abs().eval(row).unwrap(), 2.0);
+    assert_eq!((col("A") * 2.0 + col("B") / 2.0).eval(row).unwrap(), 8.5);
+    assert_eq!((-col("B")).eval(row).unwrap(), -5.0);
+
+    let error = (col("A") + col("C")).eval(row).unwrap_err();
+    assert_eq!(error.to_string(), ":2: column 'C' has bad value 'x'");
+    let error = col("D").eval(row).unwrap_err();
+    assert_eq!(error.to_string(), ":2: column 'D' is missing");
+}
+
+#[test]
+fn test_parse_expressions() {
+    let parse = |text: &str| text.parse::<Expr>();
+    assert_eq!(parse("MxT"), Ok(col("MxT")));
+    assert_eq!(parse("1HrP"), Ok(col("1HrP")));
+    assert_eq!(parse(" 2.5 "), Ok(Expr::Const(2.5)));
+    assert_eq!(parse("abs(MxT - MnT)"), Ok((col("MxT") - col("MnT")).abs()));
+    assert_eq!(parse("F-A*2"), Ok(col("F") - col("A") * 2.0));
+    assert_eq!(parse("(F - A) * 2"), Ok((col("F") - col("A")) * 2.0));
+    assert_eq!(parse("-W + -(D)"), Ok(-col("W") + -col("D")));
+    assert_eq!(parse("a - b - c"), Ok(col("a") - col("b") - col("c")));
+
+    assert_eq!(parse(""), Err("unexpected end of expression".to_string()));
+    assert_eq!(parse("abs(F"), Err("missing ')'".to_string()));
+    assert_eq!(parse("F A"), Err("unexpected 'A' in 'F A'".to_string()));
+    assert_eq!(parse("F + *"), Err("unexpected '*'".to_string()));
+
+    // Printing gives back the same expression
+    for text in [
+        "abs(MxT - MnT)",
+        "F - A * 2",
+        "(F - A) * 2",
+        "a - (b - c)",
+        "-(a + b) / 2",
+        "a - b - c",
+    ] {
+        assert_eq!(parse(text).unwrap().to_string(), text);
+    }
+}
+
+#[test]
+fn test_conditions() {
+    let table = Table::parse("A   B   C\n3   5   x\n").unwrap();
+    let row = &table.rows()[0];
+    let matches = |text: &str| text.parse::<Condition>().unwrap().matches(row);
+    assert!(matches("A < B") && matches("A <= 3") && matches("B - A == 2"));
+    assert!(matches("A * 2 > B") && matches("A >= 3") && matches("A = 3"));
+    assert!(matches("A != B") && !matches("A > B") && !matches("A != 3"));
+    assert!(!matches("C > 0") && !matches("C <= 0") && !matches("D = D"));
+
+    let parse = |text: &str| text.parse::<Condition>();
+    assert_eq!(parse("A"), Err("no comparison in 'A'".to_string()));
+    assert_eq!(parse("A ! B"), Err("unexpected '!' in 'A ! B'".to_string()));
+    assert_eq!(
+        parse("A < B < C"),
+        Err("unexpected '<' in ' B < C'".to_string())
+    );
+    assert_eq!(
+        parse("< B"),
+        Err("unexpected end of expression".to_string())
+    );
+}
+
+#[test]
+fn test_weather_and_football_queries() {
+    let weather = Table::from_file(&DATA_DIR.join("weather.dat")).unwrap();
+    let days = Query::new(&weather)
+        .argmin(&(col("MxT") - col("MnT")).abs())
+        .unwrap();
+    assert_eq!(texts(&days.value, "Dy"), ["14"]);
+
+    let football = Table::from_file(&DATA_DIR.join("football.dat")).unwrap();
+    let teams = Query::new(&football)
+        .argmin(&(col("F") - col("A")).abs())
+        .unwrap();
+    assert_eq!(texts(&teams.value, "Team"), ["Aston_Villa"]);
+}
+
+#[test]
+fn test_ties() {
+    let football = Table::from_file(&DATA_DIR.join("football.dat")).unwrap();
+    let query = Query::new(&football);
+
+    let teams = query.argmin(&col("A")).unwrap().value;
+    assert_eq!(texts(&teams, "Team"), ["Liverpool"]);
+    let teams = query.argmax(&col("Pts")).unwrap().value;
+    assert_eq!(texts(&teams, "Team"), ["Arsenal"]);
+    let teams = query.argmin(&col("P")).unwrap().value;
+    assert_eq!(teams.len(), 20); // everybody played 38 games
+
+    let teams = query.argmax(&col("A")).unwrap().value;
+    assert_eq!(texts(&teams, "Team"), ["Ipswich", "Leicester"]);
+}
+
+#[test]
+fn test_top_k_and_aggregates() {
+    let weather = Table::from_file(&DATA_DIR.join("weather.dat")).unwrap();
+    let query = Query::new(&weather);
+
+    let hottest = query.top_k(&col("MxT"), 3).unwrap().value;
+    let hottest: Vec<_> = hottest
+        .iter()
+        .map(|(row, value)| (row.text("Dy").unwrap(), *value))
+        .collect();
+    assert_eq!(hottest, [("26", 97.0), ("11", 91.0), ("27", 91.0)]);
+
+    let coldest = query.argmin(&col("MnT")).unwrap().value;
+    assert_eq!(coldest[0].get::<u32>("MnT"), Some(32));
+    let error = query.sum(&col("HDDay")).unwrap_err();
+    assert!(error.to_string().ends_with(":3: column 'HDDay' is missing"));
+    assert_eq!(query.count(), 30);
+
+    // The 'mo' summary row agrees with our own average
+    let average = query.avg(&col("MxT")).unwrap().value.unwrap();
+    let summary = weather.summaries()[0].get::<f64>("MxT").unwrap();
+    assert!((average - summary).abs() < 0.05);
+
+    // Skipping the blank heating degree cells
+    let skipping = Query::new(&weather).on_bad_rows(BadRows::Skip);
+    let heating = skipping.sum(&col("HDDay")).unwrap();
+    assert_eq!(heating.value, 16.0);
+    assert_eq!(heating.skipped.len(), 27);
+
+    let windy = query.filter(|row| row.get::<u32>("MxS").is_some_and(|x| x >= 20));
+    assert_eq!(windy.count(), 4);
+    assert_eq!(windy.avg(&col("AvSp")).unwrap().value, Some(8.375));
+
+    let empty = Query::new(&weather).filter(|_| false);
+    assert_eq!(empty.avg(&col("MxT")).unwrap().value, None);
+    assert!(empty.argmin(&col("MxT")).unwrap().value.is_empty());
+}
+
+#[test]
+fn test_group_by() {
+    let weather = Table::from_file(&DATA_DIR.join("weather.dat")).unwrap();
+    let skipping = Query::new(&weather).on_bad_rows(BadRows::Skip);
+
+    let by_type = skipping.group_by("WxType").unwrap();
+    assert_eq!(by_type.skipped.len(), 7); // no weather type
+    let counts = by_type.count().unwrap().value;
+    assert_eq!(counts[0], ("F".to_string(), 5.0));
+    assert_eq!(counts.iter().map(|x| x.1).sum::<f64>(), 23.0);
+
+    let football = Table::from_file(&DATA_DIR.join("football.dat")).unwrap();
+    let query = Query::new(&football);
+    let by_points = query.group_by("Pts").unwrap().count().unwrap().value;
+    assert_eq!(by_points.iter().filter(|x| x.1 > 1.0).count(), 4); // 50, 45, 44 and 40
+
+    let goals = query.group_by("P").unwrap().sum(&col("F")).unwrap().value;
+    assert_eq!(goals, [("38".to_string(), 1001.0)]);
+    let against = query.group_by("P").unwrap().avg(&col("A")).unwrap().value;
+    assert_eq!(against, [("38".to_string(), 1001.0 / 20.0)]);
+}
@@ -0,0 +1,408 @@
+use super::error::MungeError;
+use super::query::{Answer, BadRows};
+use super::stream;
+use super::table::{Record, Table};
+use super::types::{Date, Decimal, Spread};
+use crate::common::DATA_DIR;
+
+use std::path::Path;
+use std::str::FromStr;
+
+// Key and the two values of a data row, the caller picks the value type
+type ParsedRow<Key, Value> = Result<(Key, Value, Value), MungeError>;
+
+// Flags like "97*" are dropped here, they are still there in 'Record::cell'
+fn parse_weather(row: &Record) -> ParsedRow<u8, i64> {
+    Ok((row.value("Dy")?, row.value("MxT")?, row.value("MnT")?))
+}
+
+fn parse_football(row: &Record) -> ParsedRow<String, u32> {
+    Ok((row.value("Team")?, row.value("F")?, row.value("A")?))
+}
+
+// One pass keeping the first row with the smallest spread, a bad row is
+// skipped if asked, but broken input always stops
+fn smallest_spread<Key, Value: Spread>(
+    filename: &Path,
+    rows: impl Iterator<Item = ParsedRow<Key, Value>>,
+    bad_rows: BadRows,
+) -> Result<Answer<Key>, MungeError> {
+    let mut best: Option<(Key, Value::Diff)> = None;
+    let mut skipped = Vec::new();
+    for row in rows {
+        match row {
+            Ok((key, first, second)) => {
+                // A NaN spread so far is beaten by anything
+                let spread = first.spread(&second);
+                if best
+                    .as_ref()
+                    .is_none_or(|x| spread < x.1 || x.1.partial_cmp(&x.1).is_none())
+                {
+                    best = Some((key, spread));
+                }
+            }
+            Err(error @ MungeError::BadRow { .. }) if bad_rows == BadRows::Skip => {
+                skipped.push(error)
+            }
+            Err(error) => return Err(error),
+        }
+    }
+    let (key, _) = best.ok_or_else(|| MungeError::NoRows {
+        file: filename.to_path_buf(),
+    })?;
+    Ok(Answer {
+        value: key,
+        skipped,
+    })
+}
+
+// Header, separator and summary lines are recognized by the table itself
+fn get_smallest_spread<Key, Value: Spread>(
+    filename: &Path,
+    parser: &dyn Fn(&Record) -> ParsedRow<Key, Value>,
+    bad_rows: BadRows,
+) -> Result<Answer<Key>, MungeError> {
+    let table = Table::from_file(filename)?;
+    smallest_spread(filename, table.rows().iter().map(parser), bad_rows)
+}
+
+// The same in constant memory, for big files, gzip files and "-" for stdin
+fn get_smallest_spread_streaming<Key, Value: Spread>(
+    filename: &Path,
+    parser: &dyn Fn(&Record) -> ParsedRow<Key, Value>,
+    bad_rows: BadRows,
+) -> Result<Answer<Key>, MungeError> {
+    let (reader, format) = stream::open(filename, None)?;
+    let rows = stream::rows(filename, reader, format).without_summaries();
+    smallest_spread(filename, rows.map(|row| parser(&row?)), bad_rows)
+}
+
+fn get_smallest_spread_weather(filename: &Path, bad_rows: BadRows) -> Result<u8, MungeError> {
+    get_smallest_spread(filename, &parse_weather, bad_rows).map(|x| x.value)
+}
+
+fn get_smallest_spread_football(filename: &Path, bad_rows: BadRows) -> Result<String, MungeError> {
+    get_smallest_spread(filename, &parse_football, bad_rows).map(|x| x.value)
+}
+
+// Columns are found by their header names, so no parser is needed per file,
+// the value type is picked by the caller: i64, f64, Decimal<2>, Date...
+fn get_smallest_spread_columns<Value: Spread + FromStr>(
+    filename: &Path,
+    key: &str,
+    first: &str,
+    second: &str,
+    bad_rows: BadRows,
+) -> Result<Answer<String>, MungeError> {
+    let parser = |row: &Record| -> ParsedRow<String, Value> {
+        Ok((row.value(key)?, row.value(first)?, row.value(second)?))
+    };
+    get_smallest_spread(filename, &parser, bad_rows)
+}
+
+#[test]
+fn test_weather_spread() {
+    let filename = DATA_DIR.join("weather.dat");
+    let spread = get_smallest_spread_weather(&filename, BadRows::Fail);
+    assert_eq!(14, spread.unwrap());
+}
+
+#[test]
+fn test_football_spread() {
+    let filename = DATA_DIR.join("football.dat");
+    let spread = get_smallest_spread_football(&filename, BadRows::Fail);
+    assert_eq!("Aston_Villa", spread.unwrap());
+}
+
+#[test]
+fn test_spread_by_columns() {
+    let weather = DATA_DIR.join("weather.dat");
+    let spread =
+        get_smallest_spread_columns::<i64>(&weather, "Dy", "MxT", "MnT", BadRows::Skip).unwrap();
+    assert_eq!("14", spread.value);
+    assert!(spread.skipped.is_empty()); // the 'mo' row is a summary, not a bad row
+
+    let football = DATA_DIR.join("football.dat");
+    let spread =
+        get_smallest_spread_columns::<i64>(&football, "Team", "F", "A", BadRows::Fail).unwrap();
+    assert_eq!("Aston_Villa", spread.value);
+    assert!(spread.skipped.is_empty());
+}
+
+fn error_messages(errors: &[MungeError]) -> Vec<String> {
+    // Drop the directory part to keep the messages short
+    let prefix = DATA_DIR.to_str().unwrap().to_string() + "/";
+    errors
+        .iter()
+        .map(|x| x.to_string().replace(&prefix, ""))
+        .collect()
+}
+
+#[test]
+fn test_weather_bad_rows() {
+    let filename = DATA_DIR.join("weather_bad.dat");
+    let error = get_smallest_spread_weather(&filename, BadRows::Fail).unwrap_err();
+    assert_eq!(
+        error_messages(&[error]),
+        ["weather_bad.dat:7: column 'MxT' has bad value '9O'"]
+    );
+
+    // -59 is a fine temperature, but not an u8
+    let spread = get_smallest_spread(&filename, &parse_weather, BadRows::Skip).unwrap();
+    assert_eq!(14, spread.value);
+    assert_eq!(
+        error_messages(&spread.skipped),
+        [
+            "weather_bad.dat:7: column 'MxT' has bad value '9O'",
+            "weather_bad.dat:10: column 'MnT' is missing",
+        ]
+    );
+
+    let spread =
+        get_smallest_spread_columns::<u8>(&filename, "Dy", "MxT", "MnT", BadRows::Skip).unwrap();
+    assert_eq!("14", spread.value);
+    assert_eq!(
+        error_messages(&spread.skipped[2..]),
+        ["weather_bad.dat:13: column 'MnT' has bad value '-59'"]
+    );
+}
+
+#[test]
+fn test_football_bad_rows() {
+    let filename = DATA_DIR.join("football_bad.dat");
+    let error = get_smallest_spread_football(&filename, BadRows::Fail).unwrap_err();
+    assert_eq!(
+        error_messages(&[error]),
+        ["football_bad.dat:2: column 'F' has bad value '7g'"]
+    );
+
+    let spread = get_smallest_spread(&filename, &parse_football, BadRows::Skip).unwrap();
+    assert_eq!("Aston_Villa", spread.value);
+    assert_eq!(
+        error_messages(&spread.skipped),
+        [
+            "football_bad.dat:2: column 'F' has bad value '7g'",
+            "football_bad.dat:6: column 'F' is missing",
+        ]
+    );
+
+    let spread =
+        get_smallest_spread_columns::<i64>(&filename, "Team", "F", "A", BadRows::Skip).unwrap();
+    assert_eq!("Aston_Villa", spread.value);
+    assert_eq!(
+        error_messages(&spread.skipped),
+        [
+            "football_bad.dat:2: column 'F' has bad value '7g'",
+            "football_bad.dat:6: column 'F' is missing",
+        ]
+    );
+}
+
+#[test]
+fn test_missing_and_empty_files() {
+    let missing = DATA_DIR.join("no_such_file.dat");
+    let error = get_smallest_spread_weather(&missing, BadRows::Skip).unwrap_err();
+    assert!(matches!(error, MungeError::Io { .. }));
+    let error = get_smallest_spread_columns::<i64>(&missing, "Dy", "MxT", "MnT", BadRows::Skip);
+    assert!(matches!(error, Err(MungeError::Io { .. })));
+
+    // Only the header, so there is no answer even when skipping
+    let filename = std::env::temp_dir().join(format!("munging_empty_{}.dat", std::process::id()));
+    std::fs::write(&filename, "  Dy MxT   MnT\n\n").unwrap();
+    let error = get_smallest_spread_weather(&filename, BadRows::Skip).unwrap_err();
+    assert!(matches!(error, MungeError::NoRows { .. }));
+    let error = get_smallest_spread_columns::<i64>(&filename, "Dy", "MxT", "MnT", BadRows::Skip);
+    assert!(matches!(error, Err(MungeError::NoRows { .. })));
+    std::fs::remove_file(&filename).unwrap();
+}
+
+#[test]
+fn test_spread_all_formats() {
+    for extension in ["dat", "csv", "tsv", "jsonl"] {
+        let weather = DATA_DIR.join("weather").with_extension(extension);
+        let football = DATA_DIR.join("football").with_extension(extension);
+        assert_eq!(
+            14,
+            get_smallest_spread_weather(&weather, BadRows::Fail).unwrap()
+        );
+        assert_eq!(
+            "Aston_Villa",
+            get_smallest_spread_football(&football, BadRows::Fail).unwrap()
+        );
+
+        // The same cells in every format
+        let table = Table::from_file(&weather).unwrap();
+        assert_eq!(table.rows().len(), 30, "{}", extension);
+        assert_eq!(table.summaries().len(), 1, "{}", extension);
+        assert_eq!(table.columns().len(), 17, "{}", extension);
+        let day26 = &table.rows()[25];
+        assert_eq!(day26.cell("MxT").flag, Some('*'), "{}", extension);
+        assert_eq!(day26.text("PDir"), Some("050"), "{}", extension);
+        assert_eq!(day26.text("HDDay"), None, "{}", extension);
+        assert_eq!(
+            table.summaries()[0].get::<f32>("SkyC"),
+            Some(5.3),
+            "{}",
+            extension
+        );
+
+        let table = Table::from_file(&football).unwrap();
+        assert_eq!(table.rows().len(), 20, "{}", extension);
+        assert_eq!(
+            table.rows()[7].text("Team"),
+            Some("Aston_Villa"),
+            "{}",
+            extension
+        );
+        assert_eq!(table.rows()[7].get::<u8>("Pts"), Some(50), "{}", extension);
+    }
+}
+
+#[test]
+fn test_spread_streaming() {
+    let files = ["weather", "weather_bad", "football", "football_bad"];
+    for extension in ["dat", "csv", "tsv", "jsonl"] {
+        for name in files {
+            let filename = DATA_DIR.join(name).with_extension(extension);
+            if !filename.exists() {
+                continue;
+            }
+            let parser = match name.starts_with("weather") {
+                true => |row: &Record| {
+                    Ok((
+                        row.value::<String>("Dy")?,
+                        row.value::<i64>("MxT")?,
+                        row.value("MnT")?,
+                    ))
+                },
+                false => |row: &Record| {
+                    Ok((
+                        row.value::<String>("Team")?,
+                        row.value::<i64>("F")?,
+                        row.value("A")?,
+                    ))
+                },
+            };
+            for bad_rows in [BadRows::Fail, BadRows::Skip] {
+                let expected = get_smallest_spread(&filename, &parser, bad_rows);
+                let streamed = get_smallest_spread_streaming(&filename, &parser, bad_rows);
+                match (expected, streamed) {
+                    (Ok(expected), Ok(streamed)) => {
+                        assert_eq!(expected.value, streamed.value, "{:?}", filename);
+                        assert_eq!(
+                            error_messages(&expected.skipped),
+                            error_messages(&streamed.skipped)
+                        );
+                    }
+                    (Err(expected), Err(streamed)) => {
+                        assert_eq!(expected.to_string(), streamed.to_string())
+                    }
+                    _ => panic!("{:?} {:?}", filename, bad_rows),
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_spread_types() {
+    let weather = DATA_DIR.join("weather.dat");
+    let by_type = |spread: Result<Answer<String>, MungeError>| spread.unwrap().value;
+    let columns = |first, second| {
+        [
+            by_type(get_smallest_spread_columns::<u8>(
+                &weather,
+                "Dy",
+                first,
+                second,
+                BadRows::Skip,
+            )),
+            by_type(get_smallest_spread_columns::<i64>(
+                &weather,
+                "Dy",
+                first,
+                second,
+                BadRows::Skip,
+            )),
+            by_type(get_smallest_spread_columns::<f64>(
+                &weather,
+                "Dy",
+                first,
+                second,
+                BadRows::Skip,
+            )),
+            by_type(get_smallest_spread_columns::<Decimal<1>>(
+                &weather,
+                "Dy",
+                first,
+                second,
+                BadRows::Skip,
+            )),
+        ]
+    };
+    assert_eq!(columns("MxT", "MnT"), ["14", "14", "14", "14"]);
+
+    // Pressures have decimals, so only the float and the decimal read them
+    let integers =
+        get_smallest_spread_columns::<i64>(&weather, "Dy", "AvSLP", "AvDP", BadRows::Skip);
+    assert!(matches!(integers, Err(MungeError::NoRows { .. })));
+    let integers =
+        get_smallest_spread_columns::<Decimal<0>>(&weather, "Dy", "AvSLP", "AvDP", BadRows::Skip);
+    assert!(matches!(integers, Err(MungeError::NoRows { .. })));
+    let decimals =
+        get_smallest_spread_columns::<Decimal<1>>(&weather, "Dy", "AvSLP", "AvDP", BadRows::Fail);
+    assert_eq!(decimals.unwrap().value, "12");
+    let floats = get_smallest_spread_columns::<f64>(&weather, "Dy", "AvSLP", "AvDP", BadRows::Fail);
+    assert_eq!(floats.unwrap().value, "12");
+
+    // Below zero, far beyond u8, fractions and dates
+    let filename = std::env::temp_dir().join(format!("munging_types_{}.dat", std::process::id()));
+    let content = "\
+Id  Low          High         Opened      Closed
+a   -40          -38.5        2023-12-31  2024-03-01
+b   -5000000000  4000000000   2024-02-28  2024-03-01
+c   7.25         9            1999-01-01  2024-01-01
+";
+    std::fs::write(&filename, content).unwrap();
+    let spread = |first, second| -> [String; 3] {
+        [
+            by_type(get_smallest_spread_columns::<i64>(
+                &filename,
+                "Id",
+                first,
+                second,
+                BadRows::Skip,
+            )),
+            by_type(get_smallest_spread_columns::<f64>(
+                &filename,
+                "Id",
+                first,
+                second,
+                BadRows::Skip,
+            )),
+            by_type(get_smallest_spread_columns::<Decimal<2>>(
+                &filename,
+                "Id",
+                first,
+                second,
+                BadRows::Skip,
+            )),
+        ]
+    };
+    assert_eq!(spread("Low", "High"), ["b", "a", "a"]);
+    let dates =
+        get_smallest_spread_columns::<Date>(&filename, "Id", "Opened", "Closed", BadRows::Fail);
+    assert_eq!(dates.unwrap().value, "b");
+    let error = get_smallest_spread_columns::<u32>(&filename, "Id", "Low", "High", BadRows::Skip);
+    assert!(matches!(error, Err(MungeError::NoRows { .. })));
+    std::fs::remove_file(&filename).unwrap();
+
+    // NaN can't be compared, it is not the smallest spread
+    let rows = [
+        ("nan", f64::NAN, 1.0),
+        ("five", 5.0, 1.0),
+        ("three", 3.0, 1.0),
+    ];
+    let answer = smallest_spread(&filename, rows.into_iter().map(Ok), BadRows::Fail);
+    assert_eq!(answer.unwrap().value, "three");
+}
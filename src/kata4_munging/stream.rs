@@ -0,0 +1,410 @@
+use super::error::MungeError;
+use super::formats::{
+    check_width, csv_record_complete, json_object, non_empty, split_csv, split_tsv, Format,
+};
+use super::table::{Header, Record, SummaryRule};
+
+use flate2::read::MultiGzDecoder;
+
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+// "-" is the standard input and a ".gz" file is unpacked on the fly. Without
+// an explicit format it is told by the extension, the one under ".gz" if any.
+pub fn open(path: &Path, format: Option<Format>) -> Result<(Box<dyn BufRead>, Format), MungeError> {
+    if path == Path::new("-") {
+        return Ok((
+            Box::new(std::io::stdin().lock()),
+            format.unwrap_or(Format::Fixed),
+        ));
+    }
+    let io_error = |error| MungeError::Io {
+        file: path.to_path_buf(),
+        error,
+    };
+    let file = File::open(path).map_err(io_error)?;
+    if path.extension().is_some_and(|x| x == "gz") {
+        let format = format.unwrap_or_else(|| Format::from_path(&path.with_extension("")));
+        Ok((Box::new(BufReader::new(MultiGzDecoder::new(file))), format))
+    } else {
+        Ok((
+            Box::new(BufReader::new(file)),
+            format.unwrap_or_else(|| Format::from_path(path)),
+        ))
+    }
+}
+
+// Records read one by one, only the current row (and the next one, when the
+// summaries are dropped) is kept in memory
+pub struct Rows<R> {
+    source: PathBuf,
+    reader: R,
+    format: Format,
+    header: Option<Rc<Header>>,
+    line: usize,
+    text: String,
+    // When the summaries are dropped, the rule of the tables and the row read
+    // ahead, since only the last row may be a summary
+    summaries: Option<SummaryRule>,
+    ahead: Option<Record>,
+    done: bool,
+}
+
+pub fn rows<R: BufRead>(source: &Path, reader: R, format: Format) -> Rows<R> {
+    // JSON lines have no header line, the columns come with the keys
+    let header = match format {
+        Format::JsonLines => Some(Rc::new(Header::from_names(source.to_path_buf(), vec![]))),
+        _ => None,
+    };
+    Rows {
+        source: source.to_path_buf(),
+        reader,
+        format,
+        header,
+        line: 0,
+        text: String::new(),
+        summaries: None,
+        ahead: None,
+        done: false,
+    }
+}
+
+impl<R: BufRead> Rows<R> {
+    // Summaries (like 'mo') are dropped by the same rule as in a table
+    pub fn without_summaries(mut self) -> Rows<R> {
+        self.summaries = Some(SummaryRule::default());
+        self
+    }
+
+    // Grows for JSON lines when a new key appears
+    pub fn header(&self) -> Option<&Rc<Header>> {
+        self.header.as_ref()
+    }
+
+    // Next line into 'text' with its line break, false at the end of input
+    fn read_line(&mut self) -> Result<bool, MungeError> {
+        self.text.clear();
+        match self.reader.read_line(&mut self.text) {
+            Ok(0) => Ok(false),
+            Ok(_) => {
+                self.line += 1;
+                Ok(true)
+            }
+            Err(error) => Err(MungeError::Io {
+                file: self.source.clone(),
+                error,
+            }),
+        }
+    }
+
+    fn next_record(&mut self) -> Result<Option<Record>, MungeError> {
+        loop {
+            if !self.read_line()? {
+                return Ok(None);
+            }
+            let line_number = self.line;
+            let line = self.text.trim_end_matches(['\n', '\r']);
+            let record = match (self.format, &self.header) {
+                (Format::Fixed, _) if line.trim().is_empty() => None,
+                (Format::Fixed, None) => {
+                    self.header = Some(Rc::new(Header::parse(self.source.clone(), line)));
+                    None
+                }
+                (Format::Fixed, Some(_)) if line.trim().chars().all(|c| c == '-') => None,
+                (Format::Fixed, Some(header)) => Some(Record::parse(header, line, line_number)),
+                (Format::Tsv, _) if line.is_empty() => None,
+                (Format::Tsv, None) => {
+                    let names = split_tsv(line);
+                    self.header = Some(Rc::new(Header::from_names(self.source.clone(), names)));
+                    None
+                }
+                (Format::Tsv, Some(header)) => {
+                    let fields = split_tsv(line);
+                    check_width(&self.source, line_number, &fields, header.columns().len())?;
+                    let cells = fields.into_iter().map(non_empty).collect();
+                    Some(Record::new(header, cells, line_number))
+                }
+                (Format::Csv, _) => self.csv_record()?,
+                (Format::JsonLines, _) if line.trim().is_empty() => None,
+                (Format::JsonLines, _) => Some(self.json_record()?),
+            };
+            if record.is_some() {
+                return Ok(record);
+            }
+        }
+    }
+
+    // The row after this one is read first, to know if this one is the last
+    fn next_data_record(&mut self) -> Result<Option<Record>, MungeError> {
+        let record = match self.ahead.take() {
+            Some(record) => record,
+            None => match self.next_record()? {
+                Some(record) => record,
+                None => return Ok(None),
+            },
+        };
+        self.ahead = self.next_record()?;
+        let rule = self.summaries.as_mut().unwrap();
+        match rule.is_data(&record, self.ahead.is_none())? {
+            true => Ok(Some(record)),
+            false => Ok(None),
+        }
+    }
+
+    // Lines are joined until the quotes are balanced, a field may hold line breaks
+    fn csv_record(&mut self) -> Result<Option<Record>, MungeError> {
+        let first_line = self.line;
+        let mut text = std::mem::take(&mut self.text);
+        while !csv_record_complete(&text) && self.read_line()? {
+            text.push_str(&self.text);
+        }
+        let mut records = split_csv(&self.source, &text, first_line)?;
+        self.text = text;
+        let Some((line, fields)) = records.pop() else {
+            return Ok(None);
+        };
+        match &self.header {
+            None => {
+                self.header = Some(Rc::new(Header::from_names(self.source.clone(), fields)));
+                Ok(None)
+            }
+            Some(header) => {
+                check_width(&self.source, line, &fields, header.columns().len())?;
+                let cells = fields.into_iter().map(non_empty).collect();
+                Ok(Some(Record::new(header, cells, line)))
+            }
+        }
+    }
+
+    fn json_record(&mut self) -> Result<Record, MungeError> {
+        let pairs = json_object(&self.source, self.text.trim_end(), self.line)?;
+        let header = self.header.as_ref().unwrap();
+        let mut names: Vec<String> = header.columns().iter().map(|c| c.name.clone()).collect();
+        let old_count = names.len();
+        let mut cells = vec![None; old_count];
+        for (key, value) in pairs {
+            let column = match names.iter().position(|x| *x == key) {
+                Some(column) => column,
+                None => {
+                    names.push(key);
+                    cells.push(None);
+                    names.len() - 1
+                }
+            };
+            cells[column] = value;
+        }
+        if names.len() > old_count {
+            self.header = Some(Rc::new(Header::from_names(self.source.clone(), names)));
+        }
+        Ok(Record::new(self.header.as_ref().unwrap(), cells, self.line))
+    }
+}
+
+// The first error ends the rows, there is no telling where the next row starts
+impl<R: BufRead> Iterator for Rows<R> {
+    type Item = Result<Record, MungeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let record = match self.summaries {
+            Some(_) => self.next_data_record(),
+            None => self.next_record(),
+        };
+        let record = record.transpose();
+        self.done = !matches!(record, Some(Ok(_)));
+        record
+    }
+}
+
+//////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+use super::table::Table;
+#[cfg(test)]
+use crate::common::DATA_DIR;
+#[cfg(test)]
+use std::io::{Read, Write};
+
+// Whole input as a table, to compare with the file readers
+#[cfg(test)]
+fn read_table(path: &Path, format: Option<Format>) -> Result<Table, MungeError> {
+    let (reader, format) = open(path, format)?;
+    let mut rows = rows(path, reader, format);
+    let records = rows.by_ref().collect::<Result<Vec<_>, _>>()?;
+    let header = match rows.header() {
+        Some(header) => Rc::clone(header),
+        None => Rc::new(Header::from_names(path.to_path_buf(), vec![])),
+    };
+    Table::new(header, records)
+}
+
+#[cfg(test)]
+const FIXTURES: [&str; 10] = [
+    "weather.dat",
+    "weather.csv",
+    "weather.tsv",
+    "weather.jsonl",
+    "weather_bad.dat",
+    "football.dat",
+    "football.csv",
+    "football.tsv",
+    "football.jsonl",
+    "football_bad.dat",
+];
+
+#[cfg(test)]
+fn texts(record: &Record, columns: &[String]) -> (usize, Vec<Option<String>>) {
+    let cells = columns
+        .iter()
+        .map(|c| record.text(c).map(String::from))
+        .collect();
+    (record.line(), cells)
+}
+
+#[cfg(test)]
+fn gzip(content: &[u8], name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("munging_{}_{}", std::process::id(), name));
+    let mut encoder =
+        flate2::write::GzEncoder::new(File::create(&path).unwrap(), Default::default());
+    encoder.write_all(content).unwrap();
+    encoder.finish().unwrap();
+    path
+}
+
+#[test]
+fn test_stream_matches_table() {
+    for name in FIXTURES {
+        let path = DATA_DIR.join(name);
+        let table = Table::from_file(&path).unwrap();
+        let columns: Vec<String> = table.columns().iter().map(|c| c.name.clone()).collect();
+        let expected: Vec<_> = table.rows().iter().map(|x| texts(x, &columns)).collect();
+
+        let (reader, format) = open(&path, None).unwrap();
+        let streamed: Vec<_> = rows(&path, reader, format)
+            .without_summaries()
+            .map(|x| texts(&x.unwrap(), &columns))
+            .collect();
+        assert_eq!(streamed, expected, "{}", name);
+
+        let read = read_table(&path, None).unwrap();
+        assert_eq!(read.columns(), table.columns(), "{}", name);
+        assert_eq!(read.rows().len(), table.rows().len(), "{}", name);
+        assert_eq!(read.summaries().len(), table.summaries().len(), "{}", name);
+    }
+}
+
+#[test]
+fn test_gzip_input() {
+    for name in ["weather.dat", "football.csv", "weather.jsonl"] {
+        let content = std::fs::read(DATA_DIR.join(name)).unwrap();
+        let packed = gzip(&content, &format!("{}.gz", name));
+        let table = read_table(&packed, None).unwrap();
+        let expected = Table::from_file(&DATA_DIR.join(name)).unwrap();
+        assert_eq!(table.columns(), expected.columns(), "{}", name);
+        assert_eq!(table.rows().len(), expected.rows().len(), "{}", name);
+
+        // Any format can be forced, so a '.gz' without a second extension works too
+        let (mut reader, format) = open(&packed, Some(Format::Tsv)).unwrap();
+        assert_eq!(format, Format::Tsv);
+        let mut unpacked = Vec::new();
+        reader.read_to_end(&mut unpacked).unwrap();
+        assert_eq!(unpacked, content);
+        std::fs::remove_file(packed).unwrap();
+    }
+}
+
+#[test]
+fn test_stream_errors() {
+    let source = Path::new("test.csv");
+    let content = "A,B\n1,\"two\nlines\"\n3,4\n5,x\"y\n7,8\n";
+    let rows: Vec<_> = rows(source, content.as_bytes(), Format::Csv).collect();
+    assert_eq!(rows.len(), 3);
+    assert_eq!(rows[0].as_ref().unwrap().text("B"), Some("two\nlines"));
+    assert_eq!(rows[1].as_ref().unwrap().line(), 4);
+    let error = rows[2].as_ref().unwrap_err().to_string();
+    assert_eq!(error, "test.csv:5: quote inside unquoted field");
+
+    // A short row is an error, not a row with missing cells
+    for (name, content) in [
+        ("test.csv", "A,B\n1,2\n3\n"),
+        ("test.tsv", "A\tB\n1\t2\n3\n"),
+    ] {
+        let path = Path::new(name);
+        let format = Format::from_path(path);
+        let error = super::stream::rows(path, content.as_bytes(), format).last();
+        assert_eq!(
+            error.unwrap().unwrap_err().to_string(),
+            format!("{}:3: expected 2 fields, found 1", name)
+        );
+    }
+
+    let content = "A,B\n1,\"open\n2,3\n";
+    let error = super::stream::rows(source, content.as_bytes(), Format::Csv).last();
+    assert_eq!(
+        error.unwrap().unwrap_err().to_string(),
+        "test.csv:2: unterminated quote"
+    );
+
+    // Garbage in a gzip file is an I/O error
+    let packed = gzip(b"Dy MxT\n1 2\n", "broken.dat.gz");
+    let mut bytes = std::fs::read(&packed).unwrap();
+    bytes.truncate(bytes.len() - 12);
+    std::fs::write(&packed, bytes).unwrap();
+    let error = read_table(&packed, None).err().unwrap();
+    assert!(matches!(error, MungeError::Io { .. }));
+    std::fs::remove_file(packed).unwrap();
+
+    let error = read_table(&DATA_DIR.join("no_such_file.dat.gz"), None)
+        .err()
+        .unwrap();
+    assert!(matches!(error, MungeError::Io { .. }));
+}
+
+// Lines are made on the fly, so the input is never in memory as a whole
+#[cfg(test)]
+struct Generated {
+    rows: usize,
+    next: usize,
+    pending: Vec<u8>,
+}
+
+#[cfg(test)]
+impl Read for Generated {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.pending.is_empty() && self.next <= self.rows {
+            self.pending = match self.next {
+                0 => b"  Dy MxT   MnT\n".to_vec(),
+                n => format!("{:>4} {:>3}  {:>3}\n", n, 50 + n % 40, 40 + n % 7).into_bytes(),
+            };
+            self.next += 1;
+        }
+        let count = buf.len().min(self.pending.len());
+        buf[..count].copy_from_slice(&self.pending[..count]);
+        self.pending.drain(..count);
+        Ok(count)
+    }
+}
+
+#[test]
+fn test_stream_many_rows() {
+    let generated = Generated {
+        rows: 200_000,
+        next: 0,
+        pending: Vec::new(),
+    };
+    let reader = BufReader::new(generated);
+    let mut count = 0;
+    let mut smallest = (i64::MAX, 0);
+    for row in rows(Path::new("generated.dat"), reader, Format::Fixed).without_summaries() {
+        let row = row.unwrap();
+        let spread = row.get::<i64>("MxT").unwrap() - row.get::<i64>("MnT").unwrap();
+        smallest = smallest.min((spread.abs(), row.get("Dy").unwrap()));
+        count += 1;
+    }
+    assert_eq!(count, 200_000);
+    assert_eq!(smallest, (4, 160));
+}
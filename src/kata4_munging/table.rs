@@ -0,0 +1,434 @@
+use super::cell::{split_flag, Cell, Kind};
+use super::error::{BadCell, MungeError};
+use super::formats::{read_csv, read_json_lines, read_tsv, Format, RawTable};
+
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::str::FromStr;
+
+// Column is located by the position of its name in the header line,
+// the position is not used for the delimited formats
+#[derive(Debug, PartialEq)]
+pub struct Column {
+    pub name: String,
+    start: usize,
+    end: usize,
+}
+
+#[derive(Debug)]
+pub struct Header {
+    source: PathBuf,
+    columns: Vec<Column>,
+}
+
+impl Header {
+    pub fn parse(source: PathBuf, line: &str) -> Header {
+        let columns = tokens(line)
+            .map(|(start, text)| Column {
+                name: text.to_string(),
+                start,
+                end: start + text.len(),
+            })
+            .collect();
+        Header { source, columns }
+    }
+
+    pub fn from_names(source: PathBuf, names: Vec<String>) -> Header {
+        let columns = names
+            .into_iter()
+            .map(|name| Column {
+                name,
+                start: 0,
+                end: 0,
+            })
+            .collect();
+        Header { source, columns }
+    }
+
+    pub fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    pub fn index(&self, name: &str) -> Option<usize> {
+        self.columns.iter().position(|c| c.name == name)
+    }
+
+    // The column sharing most bytes with the span, or the one it touches, so
+    // that a right-aligned '9' just after the 'W' header still belongs to it
+    fn column_at(&self, start: usize, end: usize) -> Option<usize> {
+        self.columns
+            .iter()
+            .map(|c| end.min(c.end) as isize - start.max(c.start) as isize)
+            .enumerate()
+            .filter(|&(_, overlap)| overlap >= 0)
+            .max_by_key(|&(_, overlap)| overlap)
+            .map(|(index, _)| index)
+    }
+}
+
+// Whitespace separated words with their byte offsets
+fn tokens(line: &str) -> impl Iterator<Item = (usize, &str)> {
+    line.split(char::is_whitespace)
+        .filter(|s| !s.is_empty())
+        .map(move |s| (s.as_ptr() as usize - line.as_ptr() as usize, s))
+}
+
+#[derive(Debug)]
+pub struct Record {
+    header: Rc<Header>,
+    cells: Vec<Option<String>>,
+    line: usize,
+}
+
+impl Record {
+    // Words are put into the column they overlap most, the others (like the
+    // row numbers and the '-' in football.dat) do not belong to any column
+    pub fn parse(header: &Rc<Header>, line: &str, line_number: usize) -> Record {
+        let mut cells: Vec<Option<String>> = vec![None; header.columns.len()];
+        for (start, text) in tokens(line) {
+            if let Some(index) = header.column_at(start, start + text.len()) {
+                match &mut cells[index] {
+                    Some(cell) => {
+                        cell.push(' ');
+                        cell.push_str(text);
+                    }
+                    cell => *cell = Some(text.to_string()),
+                }
+            }
+        }
+        Record {
+            header: Rc::clone(header),
+            cells,
+            line: line_number,
+        }
+    }
+
+    // Cells in the order of the header columns, None for the empty ones
+    pub fn new(header: &Rc<Header>, cells: Vec<Option<String>>, line: usize) -> Record {
+        Record {
+            header: Rc::clone(header),
+            cells,
+            line,
+        }
+    }
+
+    // Kind of the first cell tells the data rows from the summaries
+    pub fn first_kind(&self) -> Kind {
+        match self.cells.first() {
+            Some(text) => Cell::parse(text.as_deref()).kind(),
+            None => Kind::Missing,
+        }
+    }
+
+    // Raw cell text, None for a blank cell or an unknown column
+    pub fn text(&self, name: &str) -> Option<&str> {
+        let index = self.header.index(name)?;
+        self.cells.get(index)?.as_deref()
+    }
+
+    // Typed cell value, the flag is dropped
+    pub fn get<T: FromStr>(&self, name: &str) -> Option<T> {
+        split_flag(self.text(name)?).0.parse().ok()
+    }
+
+    // The same as 'get', but tells where and what the bad cell is
+    pub fn value<T: FromStr>(&self, name: &str) -> Result<T, MungeError> {
+        self.get(name)
+            .ok_or_else(|| self.error(BadCell::new(name, self.text(name))))
+    }
+
+    fn key_error(&self, data_kind: Kind) -> MungeError {
+        let column = &self.header.columns[0].name;
+        let expected = match data_kind {
+            Kind::Missing => "blank",
+            Kind::Number => "a number",
+            Kind::Text => "a word",
+        };
+        let message = match self.cells[0].as_deref() {
+            Some(key) => format!(
+                "key '{}' in column '{}' is not {} like the first key",
+                key, column, expected
+            ),
+            None => format!("key in column '{}' is missing", column),
+        };
+        MungeError::Syntax {
+            file: self.header.source.clone(),
+            line: self.line,
+            message,
+        }
+    }
+
+    pub fn error(&self, cell: BadCell) -> MungeError {
+        MungeError::BadRow {
+            file: self.header.source.clone(),
+            line: self.line,
+            cell,
+        }
+    }
+}
+
+pub struct Table {
+    header: Rc<Header>,
+    rows: Vec<Record>,
+    #[cfg(test)]
+    summaries: Vec<Record>,
+}
+
+// Data rows share the kind of their key, the first cell: a day number, a team
+// name. Only the last row may be a summary like the 'mo' line of weather.dat,
+// with a word for its key under the number keys. Other odd keys are malformed.
+#[derive(Default)]
+pub struct SummaryRule {
+    data_kind: Option<Kind>,
+}
+
+impl SummaryRule {
+    // True for a data row, false for the summary
+    pub fn is_data(&mut self, record: &Record, last: bool) -> Result<bool, MungeError> {
+        let kind = record.first_kind();
+        let data_kind = *self.data_kind.get_or_insert(kind);
+        let key = record.cells.first().and_then(|x| x.as_deref());
+        if kind == data_kind {
+            Ok(true)
+        } else if last
+            && data_kind == Kind::Number
+            && key.is_some_and(|x| !x.contains(|c: char| c.is_ascii_digit()))
+        {
+            Ok(false)
+        } else {
+            Err(record.key_error(data_kind))
+        }
+    }
+}
+
+impl Table {
+    // The first non-blank line is the header, blank and '----' lines are skipped
+    fn parse_fixed(source: PathBuf, content: &str) -> Result<Table, MungeError> {
+        let mut lines = content
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty());
+        let header = match lines.next() {
+            Some((_, line)) => Header::parse(source, line),
+            None => Header::from_names(source, vec![]),
+        };
+        let header = Rc::new(header);
+        let records = lines
+            .filter(|(_, line)| !line.trim().chars().all(|c| c == '-'))
+            .map(|(index, line)| Record::parse(&header, line, index + 1))
+            .collect();
+        Table::new(header, records)
+    }
+
+    fn from_raw(source: PathBuf, raw: RawTable) -> Result<Table, MungeError> {
+        let header = Rc::new(Header::from_names(source, raw.names));
+        let records = raw
+            .rows
+            .into_iter()
+            .map(|(line, cells)| Record::new(&header, cells, line))
+            .collect();
+        Table::new(header, records)
+    }
+
+    // Summary rows are kept apart from the data rows, whatever the format is
+    pub fn new(header: Rc<Header>, records: Vec<Record>) -> Result<Table, MungeError> {
+        let mut rule = SummaryRule::default();
+        let count = records.len();
+        let mut rows = Vec::new();
+        let mut summaries = Vec::new();
+        for (index, record) in records.into_iter().enumerate() {
+            match rule.is_data(&record, index + 1 == count)? {
+                true => rows.push(record),
+                false => summaries.push(record),
+            }
+        }
+        Ok(Table {
+            header,
+            rows,
+            #[cfg(test)]
+            summaries,
+        })
+    }
+
+    pub fn read(source: &Path, content: &str, format: Format) -> Result<Table, MungeError> {
+        let source_path = source.to_path_buf();
+        match format {
+            Format::Fixed => Table::parse_fixed(source_path, content),
+            Format::Csv => Table::from_raw(source_path, read_csv(source, content)?),
+            Format::Tsv => Table::from_raw(source_path, read_tsv(source, content)?),
+            Format::JsonLines => Table::from_raw(source_path, read_json_lines(source, content)?),
+        }
+    }
+
+    // The format is told by the file extension
+    pub fn from_file(filename: &Path) -> Result<Table, MungeError> {
+        match std::fs::read_to_string(filename) {
+            Ok(content) => Table::read(filename, &content, Format::from_path(filename)),
+            Err(error) => Err(MungeError::Io {
+                file: filename.to_path_buf(),
+                error,
+            }),
+        }
+    }
+
+    pub fn columns(&self) -> &[Column] {
+        self.header.columns()
+    }
+
+    pub fn rows(&self) -> &[Record] {
+        &self.rows
+    }
+}
+
+// Only the tests look at the line numbers, the flags and the summaries
+#[cfg(test)]
+impl Record {
+    // Line number in the source file, starting from 1
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    // Value with its flag, like the '*' of the weather extremes
+    pub fn cell(&self, name: &str) -> Cell {
+        Cell::parse(self.text(name))
+    }
+}
+
+#[cfg(test)]
+impl Table {
+    pub fn parse(content: &str) -> Result<Table, MungeError> {
+        Table::parse_fixed(PathBuf::new(), content)
+    }
+
+    pub fn summaries(&self) -> &[Record] {
+        &self.summaries
+    }
+}
+
+//////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+use super::cell::Value;
+#[cfg(test)]
+use crate::common::DATA_DIR;
+
+#[test]
+fn test_weather_table() {
+    let table = Table::from_file(&DATA_DIR.join("weather.dat")).unwrap();
+    let names: Vec<_> = table.columns().iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names[..5], ["Dy", "MxT", "MnT", "AvT", "HDDay"]);
+    assert_eq!(names.len(), 17);
+    assert_eq!(table.rows().len(), 30);
+    assert_eq!(table.summaries().len(), 1); // the 'mo' row
+
+    let day1 = &table.rows()[0];
+    assert_eq!(day1.line(), 3);
+    assert_eq!(day1.get::<u8>("Dy"), Some(1));
+    assert_eq!(day1.get::<u8>("MxT"), Some(88));
+    assert_eq!(day1.text("HDDay"), None); // blank cell
+    assert_eq!(day1.text("1HrP"), None);
+    assert_eq!(day1.get::<f32>("TPcpn"), Some(0.0));
+    assert_eq!(day1.text("WxType"), Some("F"));
+    assert_eq!(day1.get::<f32>("AvSLP"), Some(1004.5));
+    assert_eq!(day1.text("Unknown"), None);
+
+    let day9 = &table.rows()[8];
+    assert_eq!(day9.text("MnT"), Some("32*"));
+    assert_eq!(day9.get::<u8>("MnT"), Some(32));
+    assert_eq!(day9.cell("MnT").value, Value::Integer(32));
+    assert_eq!(day9.cell("MnT").flag, Some('*'));
+    assert_eq!(day9.cell("MxT").flag, None);
+    assert_eq!(day9.cell("WxType").value, Value::Missing);
+    assert_eq!(day9.get::<u8>("HDDay"), Some(6));
+
+    let month = &table.summaries()[0];
+    assert_eq!(month.text("Dy"), Some("mo"));
+    assert_eq!(month.get::<u8>("Dy"), None);
+    assert_eq!(month.text("MxT"), Some("82.9"));
+    assert_eq!(month.get::<f32>("MxT"), Some(82.9));
+    assert_eq!(month.cell("HDDay").value, Value::Integer(16));
+    assert_eq!(month.cell("PDir").value, Value::Missing);
+    assert_eq!(month.text("AvSp"), Some("6.9"));
+    assert_eq!(month.text("SkyC"), Some("5.3"));
+
+    let error = month.value::<u8>("MxT").unwrap_err().to_string();
+    assert!(error.ends_with("weather.dat:33: column 'MxT' has bad value '82.9'"));
+    let error = day1.value::<u8>("HDDay").unwrap_err().to_string();
+    assert!(error.ends_with("weather.dat:3: column 'HDDay' is missing"));
+}
+
+#[test]
+fn test_football_table() {
+    let table = Table::from_file(&DATA_DIR.join("football.dat")).unwrap();
+    let names: Vec<_> = table.columns().iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, ["Team", "P", "W", "L", "D", "F", "A", "Pts"]);
+    assert_eq!(table.rows().len(), 20); // the '----' line is skipped
+    assert!(table.summaries().is_empty()); // every first cell is a name
+
+    let arsenal = &table.rows()[0];
+    assert_eq!(arsenal.text("Team"), Some("Arsenal"));
+    assert_eq!(arsenal.get::<u8>("P"), Some(38));
+    assert_eq!(arsenal.get::<u8>("F"), Some(79));
+    assert_eq!(arsenal.get::<u8>("A"), Some(36));
+    assert_eq!(arsenal.get::<u16>("Pts"), Some(87));
+
+    let ipswich = &table.rows()[17];
+    assert_eq!(ipswich.line(), 20);
+    assert_eq!(ipswich.text("Team"), Some("Ipswich"));
+    assert_eq!(ipswich.get::<u8>("W"), Some(9));
+}
+
+#[test]
+fn test_table_layout() {
+    // Words spanning one column are joined, unlabeled ones are dropped
+    let table =
+        Table::parse("\n  Title         Score\n# Big Ben       7\nSmall           10\n").unwrap();
+    assert_eq!(table.rows().len(), 2);
+    assert_eq!(table.rows()[0].text("Title"), Some("Big Ben"));
+    assert_eq!(table.rows()[0].get::<u32>("Score"), Some(7));
+    assert_eq!(table.rows()[1].text("Title"), Some("Small"));
+    assert_eq!(table.rows()[1].get::<u32>("Score"), Some(10));
+
+    // Footer is told apart by its first cell, not by the text in it
+    let table = Table::parse("Year  Rain\n2001   3.5\n2002   4.1*\n2003\nTotal  7.6\n").unwrap();
+    assert_eq!(table.rows().len(), 3);
+    assert_eq!(table.rows()[1].cell("Rain").flag, Some('*'));
+    assert_eq!(table.rows()[2].cell("Rain").value, Value::Missing);
+    assert_eq!(table.summaries().len(), 1);
+    assert_eq!(table.summaries()[0].get::<f32>("Rain"), Some(7.6));
+
+    let empty = Table::parse("").unwrap();
+    assert!(empty.columns().is_empty());
+    assert!(empty.rows().is_empty());
+}
+
+#[test]
+fn test_summary_rule() {
+    // One data row and one summary, the summary is still the last row
+    let table = Table::parse("Dy  MxT\n1   88\nmo  88\n").unwrap();
+    assert_eq!(table.rows().len(), 1);
+    assert_eq!(table.summaries()[0].text("Dy"), Some("mo"));
+
+    // A broken key is not taken for a summary, wherever it is
+    let error = |content| Table::parse(content).err().unwrap().to_string();
+    assert_eq!(
+        error("Dy  MxT\n1   88\n1O  79\n3   77\n"),
+        ":3: key '1O' in column 'Dy' is not a number like the first key"
+    );
+    assert_eq!(
+        error("Dy  MxT\n1   88\n2   79\n1O  77\n"),
+        ":4: key '1O' in column 'Dy' is not a number like the first key"
+    );
+    assert_eq!(
+        error("Dy  MxT\n1   88\nmo  79\n3   77\n"),
+        ":3: key 'mo' in column 'Dy' is not a number like the first key"
+    );
+    assert_eq!(
+        error("Dy  MxT\n1   88\n    79\n3   77\n"),
+        ":3: key in column 'Dy' is missing"
+    );
+    assert_eq!(
+        error("Team     Pts\nArsenal  87\n12       80\n"),
+        ":3: key '12' in column 'Team' is not a word like the first key"
+    );
+}
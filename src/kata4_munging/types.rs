@@ -0,0 +1,307 @@
+use std::fmt;
+use std::str::FromStr;
+
+// Values with a distance between them, like the spread of the temperatures
+pub trait Spread {
+    type Diff: PartialOrd + Copy + fmt::Debug;
+    fn spread(&self, other: &Self) -> Self::Diff;
+}
+
+// 'abs_diff' gives the unsigned type, so even i64::MIN to i64::MAX fits
+macro_rules! integer_spread {
+    ($($type:ty => $diff:ty),*) => {$(
+        impl Spread for $type {
+            type Diff = $diff;
+            fn spread(&self, other: &Self) -> $diff {
+                self.abs_diff(*other)
+            }
+        }
+    )*};
+}
+
+integer_spread!(u8 => u8, u16 => u16, u32 => u32, u64 => u64, i8 => u8, i16 => u16, i32 => u32, i64 => u64);
+
+// A NaN spread can't be compared, so it never wins over a real one
+impl Spread for f32 {
+    type Diff = f32;
+    fn spread(&self, other: &Self) -> f32 {
+        (self - other).abs()
+    }
+}
+
+impl Spread for f64 {
+    type Diff = f64;
+    fn spread(&self, other: &Self) -> f64 {
+        (self - other).abs()
+    }
+}
+
+//////////////////////////////////////////////////////////////////////////////
+// Fixed-point decimal with SCALE digits after the point: "1014.9" is 101490
+// for Decimal<2>. No rounding, a value with more digits does not parse.
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Decimal<const SCALE: u32> {
+    // Parsed values fit i64, so that any difference fits as well
+    units: i128,
+}
+
+impl<const SCALE: u32> Decimal<SCALE> {
+    pub fn new(units: i64) -> Decimal<SCALE> {
+        Decimal {
+            units: units.into(),
+        }
+    }
+
+    pub fn units(&self) -> i128 {
+        self.units
+    }
+}
+
+impl<const SCALE: u32> FromStr for Decimal<SCALE> {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let bad = || format!("bad decimal '{}'", text);
+        let (negative, digits) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, text.strip_prefix('+').unwrap_or(text)),
+        };
+        let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        let all_digits = |x: &str| x.chars().all(|c| c.is_ascii_digit());
+        if (whole.is_empty() && fraction.is_empty()) || !all_digits(whole) || !all_digits(fraction)
+        {
+            return Err(bad());
+        }
+        if fraction.len() > SCALE as usize {
+            return Err(format!("'{}' has more than {} decimals", text, SCALE));
+        }
+        let padded = format!("{}{:0<width$}", whole, fraction, width = SCALE as usize);
+        let units: i64 = padded.parse().map_err(|_| bad())?;
+        Ok(Decimal::new(if negative { -units } else { units }))
+    }
+}
+
+impl<const SCALE: u32> fmt::Display for Decimal<SCALE> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.units < 0 { "-" } else { "" };
+        let units = self.units.unsigned_abs();
+        let one = 10u128.pow(SCALE);
+        match SCALE {
+            0 => write!(f, "{}{}", sign, units),
+            _ => write!(
+                f,
+                "{}{}.{:0width$}",
+                sign,
+                units / one,
+                units % one,
+                width = SCALE as usize
+            ),
+        }
+    }
+}
+
+impl<const SCALE: u32> Spread for Decimal<SCALE> {
+    type Diff = Decimal<SCALE>;
+    fn spread(&self, other: &Self) -> Decimal<SCALE> {
+        Decimal {
+            units: (self.units - other.units).abs(),
+        }
+    }
+}
+
+//////////////////////////////////////////////////////////////////////////////
+// Calendar date as "YYYY-MM-DD", the spread is the number of days between
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Date {
+    // Days since 1970-01-01
+    days: i64,
+}
+
+fn is_leap(year: i64) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if is_leap(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Days from the civil date, counted in 400-year eras starting in March,
+// so that the leap day is the last day of a year
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+impl Date {
+    pub fn new(year: i64, month: i64, day: i64) -> Option<Date> {
+        let valid = (1..=12).contains(&month) && (1..=days_in_month(year, month)).contains(&day);
+        valid.then(|| Date {
+            days: days_from_civil(year, month, day),
+        })
+    }
+}
+
+impl FromStr for Date {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let bad = || format!("bad date '{}'", text);
+        let parts: Vec<&str> = text.split('-').collect();
+        let [year, month, day] = parts[..] else {
+            return Err(bad());
+        };
+        let number = |part: &str, width| match part.len() == width
+            && part.chars().all(|c| c.is_ascii_digit())
+        {
+            true => part.parse::<i64>().map_err(|_| bad()),
+            false => Err(bad()),
+        };
+        Date::new(number(year, 4)?, number(month, 2)?, number(day, 2)?).ok_or_else(bad)
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (year, month, day) = civil_from_days(self.days);
+        write!(f, "{:04}-{:02}-{:02}", year, month, day)
+    }
+}
+
+impl Spread for Date {
+    type Diff = u64;
+    fn spread(&self, other: &Self) -> u64 {
+        self.days.abs_diff(other.days)
+    }
+}
+
+//////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_integer_spreads() {
+    assert_eq!(97u8.spread(&64), 33);
+    assert_eq!(64u8.spread(&97), 33);
+    assert_eq!((-100i8).spread(&100), 200u8);
+    assert_eq!((-59i64).spread(&61), 120);
+    assert_eq!(i64::MIN.spread(&i64::MAX), u64::MAX);
+    assert_eq!(u64::MAX.spread(&0), u64::MAX);
+}
+
+#[test]
+fn test_float_spreads() {
+    assert_eq!(82.9f64.spread(&60.5), 82.9 - 60.5);
+    assert_eq!((-0.5f64).spread(&0.25), 0.75);
+    assert_eq!(1.5f32.spread(&-1.5), 3.0);
+    assert!(f64::NAN.spread(&1.0).partial_cmp(&0.0).is_none());
+}
+
+#[test]
+fn test_decimals() {
+    let decimal = |text: &str| text.parse::<Decimal<2>>();
+    assert_eq!(decimal("1014.9"), Ok(Decimal::new(101490)));
+    assert_eq!(decimal("-0.05"), Ok(Decimal::new(-5)));
+    assert_eq!(decimal("+7"), Ok(Decimal::new(700)));
+    assert_eq!(decimal(".5"), Ok(Decimal::new(50)));
+    assert_eq!(decimal("12."), Ok(Decimal::new(1200)));
+    assert_eq!(
+        decimal("0.005"),
+        Err("'0.005' has more than 2 decimals".to_string())
+    );
+    for bad in [
+        "",
+        ".",
+        "-",
+        "1.2.3",
+        "1e3",
+        "12a",
+        "- 1",
+        "99999999999999999999",
+    ] {
+        assert_eq!(decimal(bad), Err(format!("bad decimal '{}'", bad)));
+    }
+
+    assert_eq!(Decimal::<2>::new(101490).to_string(), "1014.90");
+    assert_eq!(Decimal::<2>::new(-5).to_string(), "-0.05");
+    assert_eq!(Decimal::<0>::new(-5).to_string(), "-5");
+    assert_eq!("3".parse::<Decimal<0>>().unwrap().units(), 3);
+
+    let spread = decimal("1008.6")
+        .unwrap()
+        .spread(&decimal("1014.9").unwrap());
+    assert_eq!(spread.to_string(), "6.30");
+    let extremes = Decimal::<0>::new(i64::MIN).spread(&Decimal::new(i64::MAX));
+    assert_eq!(extremes.units(), u64::MAX.into());
+}
+
+#[test]
+fn test_dates() {
+    let date = |text: &str| text.parse::<Date>();
+    assert_eq!(date("1970-01-01").unwrap().days, 0);
+    assert_eq!(date("1969-12-31").unwrap().days, -1);
+    assert_eq!(date("2000-03-01").unwrap().days, 11017);
+    assert_eq!(date("2024-02-29").unwrap().to_string(), "2024-02-29");
+    for bad in [
+        "2023-02-29",
+        "1900-02-29",
+        "2024-13-01",
+        "2024-00-10",
+        "2024-04-31",
+        "2024-1-01",
+        "24-01-01",
+        "2024/01/01",
+        "",
+    ] {
+        assert_eq!(date(bad), Err(format!("bad date '{}'", bad)));
+    }
+
+    // Every day of a few centuries goes there and back
+    let start = date("1899-01-01").unwrap().days;
+    let mut previous = None;
+    for days in start..start + 3 * 36525 {
+        let text = Date { days }.to_string();
+        assert_eq!(date(&text), Ok(Date { days }));
+        assert!(previous < Some(text.clone()));
+        previous = Some(text);
+    }
+
+    assert_eq!(
+        date("2023-12-31")
+            .unwrap()
+            .spread(&date("2024-03-01").unwrap()),
+        61
+    );
+    assert_eq!(
+        date("2024-03-01")
+            .unwrap()
+            .spread(&date("2023-12-31").unwrap()),
+        61
+    );
+}
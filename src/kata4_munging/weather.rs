@@ -0,0 +1,463 @@
+use super::error::MungeError;
+use super::table::{Record, Table};
+
+use std::fmt;
+use std::str::FromStr;
+
+// One row of weather.dat. Blank cells of the optional columns are None, a
+// blank HDDay means no heating was needed.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Day {
+    pub day: u8,
+    pub max_temp: i32,
+    pub min_temp: i32,
+    pub avg_temp: i32,
+    pub heating_degree_days: Option<u32>,
+    pub avg_dew_point: f64,
+    pub hour_precip: Option<f64>,
+    pub total_precip: f64,
+    pub weather: Option<String>,
+    pub peak_wind_dir: u16,
+    pub avg_wind_speed: f64,
+    pub wind_dir: u16,
+    pub max_wind_speed: u32,
+    pub sky_cover: f64,
+    pub max_humidity: u32,
+    pub min_humidity: u32,
+    pub pressure: f64,
+    // Columns with a flag, the '*' marks the extremes of the month
+    pub flagged: Vec<String>,
+}
+
+// A blank cell is fine, a bad one is not
+fn optional<T: FromStr>(row: &Record, name: &str) -> Result<Option<T>, MungeError> {
+    match row.text(name) {
+        None => Ok(None),
+        Some(_) => row.value(name).map(Some),
+    }
+}
+
+impl Day {
+    pub fn parse(row: &Record) -> Result<Day, MungeError> {
+        let flagged = COLUMNS
+            .iter()
+            .map(|x| x.0)
+            .filter(|name| row.cell(name).flag.is_some())
+            .map(String::from)
+            .collect();
+        Ok(Day {
+            day: row.value("Dy")?,
+            max_temp: row.value("MxT")?,
+            min_temp: row.value("MnT")?,
+            avg_temp: row.value("AvT")?,
+            heating_degree_days: optional(row, "HDDay")?,
+            avg_dew_point: row.value("AvDP")?,
+            hour_precip: optional(row, "1HrP")?,
+            total_precip: row.value("TPcpn")?,
+            weather: optional(row, "WxType")?,
+            peak_wind_dir: row.value("PDir")?,
+            avg_wind_speed: row.value("AvSp")?,
+            wind_dir: row.value("Dir")?,
+            max_wind_speed: row.value("MxS")?,
+            sky_cover: row.value("SkyC")?,
+            max_humidity: row.value("MxR")?,
+            min_humidity: row.value("MnR")?,
+            pressure: row.value("AvSLP")?,
+            flagged,
+        })
+    }
+}
+
+type Getter = fn(&Day) -> f64;
+
+// The numeric columns by their header names
+pub const COLUMNS: [(&str, Getter); 12] = [
+    ("MxT", |x| x.max_temp.into()),
+    ("MnT", |x| x.min_temp.into()),
+    ("AvT", |x| x.avg_temp.into()),
+    ("HDDay", |x| x.heating_degree_days.unwrap_or(0).into()),
+    ("AvDP", |x| x.avg_dew_point),
+    ("TPcpn", |x| x.total_precip),
+    ("AvSp", |x| x.avg_wind_speed),
+    ("MxS", |x| x.max_wind_speed.into()),
+    ("SkyC", |x| x.sky_cover),
+    ("MxR", |x| x.max_humidity.into()),
+    ("MnR", |x| x.min_humidity.into()),
+    ("AvSLP", |x| x.pressure),
+];
+
+pub fn column(name: &str) -> Option<Getter> {
+    COLUMNS.iter().find(|x| x.0 == name).map(|x| x.1)
+}
+
+// The 'mo' row: averages of the month, total of the degree days and precipitation
+#[derive(Clone, PartialEq, Debug)]
+pub struct Summary {
+    pub max_temp: f64,
+    pub min_temp: f64,
+    pub avg_temp: f64,
+    pub heating_degree_days: f64,
+    pub avg_dew_point: f64,
+    pub total_precip: f64,
+    pub avg_wind_speed: f64,
+    pub sky_cover: f64,
+}
+
+impl Summary {
+    fn parse(row: &Record) -> Result<Summary, MungeError> {
+        Ok(Summary {
+            max_temp: row.value("MxT")?,
+            min_temp: row.value("MnT")?,
+            avg_temp: row.value("AvT")?,
+            heating_degree_days: row.value("HDDay")?,
+            avg_dew_point: row.value("AvDP")?,
+            total_precip: row.value("TPcpn")?,
+            avg_wind_speed: row.value("AvSp")?,
+            sky_cover: row.value("SkyC")?,
+        })
+    }
+}
+
+pub struct Month {
+    pub days: Vec<Day>,
+    pub summary: Option<Summary>,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct DegreeDays {
+    pub heating: f64,
+    pub cooling: f64,
+}
+
+// Days and value of a window of consecutive rows
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Window {
+    pub first_day: u8,
+    pub last_day: u8,
+    pub mean: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+// Reported value that does not match the one computed from the days
+#[derive(Clone, PartialEq, Debug)]
+pub struct Mismatch {
+    pub what: String,
+    pub computed: f64,
+    pub reported: f64,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: reported {}, computed {}",
+            self.what, self.reported, self.computed
+        )
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Anomaly {
+    pub day: u8,
+    pub column: &'static str,
+    pub value: f64,
+    pub z_score: f64,
+}
+
+// Degree days are counted from 65°F, like in the HDDay column
+pub const BASE_TEMP: f64 = 65.0;
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+impl Month {
+    pub fn from_table(table: &Table) -> Result<Month, MungeError> {
+        let days = table
+            .rows()
+            .iter()
+            .map(Day::parse)
+            .collect::<Result<_, _>>()?;
+        let summary = table.summaries().first().map(Summary::parse).transpose()?;
+        Ok(Month { days, summary })
+    }
+
+    fn values(&self, getter: Getter) -> Vec<f64> {
+        self.days.iter().map(getter).collect()
+    }
+
+    // From the daily average temperature, the one the HDDay column uses
+    pub fn degree_days(&self, base: f64) -> DegreeDays {
+        let temps = self.values(|x| x.avg_temp.into());
+        DegreeDays {
+            heating: temps.iter().map(|x| (base - x).max(0.0)).sum(),
+            cooling: temps.iter().map(|x| (x - base).max(0.0)).sum(),
+        }
+    }
+
+    // Mean, min and max of every run of 'size' days
+    pub fn rolling(&self, getter: Getter, size: usize) -> Vec<Window> {
+        let values = self.values(getter);
+        if size == 0 {
+            return vec![];
+        }
+        values
+            .windows(size)
+            .zip(self.days.windows(size))
+            .map(|(values, days)| Window {
+                first_day: days[0].day,
+                last_day: days[size - 1].day,
+                mean: mean(values),
+                min: values.iter().copied().fold(f64::INFINITY, f64::min),
+                max: values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            })
+            .collect()
+    }
+
+    // The days more than 'threshold' standard deviations away from the mean
+    // of their column, the most unusual first. HDDay is mostly blank, so any
+    // heating day stands out there.
+    pub fn anomalies(&self, threshold: f64) -> Vec<Anomaly> {
+        let mut anomalies = Vec::new();
+        for (column, getter) in COLUMNS {
+            let values = self.values(getter);
+            let mean = mean(&values);
+            let deviation = (values.iter().map(|x| (x - mean).powi(2)).sum::<f64>()
+                / values.len() as f64)
+                .sqrt();
+            if deviation == 0.0 {
+                continue;
+            }
+            for (day, value) in self.days.iter().zip(values) {
+                let z_score = (value - mean) / deviation;
+                if z_score.abs() >= threshold {
+                    anomalies.push(Anomaly {
+                        day: day.day,
+                        column,
+                        value,
+                        z_score,
+                    });
+                }
+            }
+        }
+        anomalies.sort_by(|a, b| b.z_score.abs().total_cmp(&a.z_score.abs()));
+        anomalies
+    }
+
+    // Every HDDay against the average temperature, then the 'mo' row against
+    // the days. Its averages have one decimal, so a half of it is fine, and
+    // its AvT is the mean of the MxT and MnT averages, not of the AvT column.
+    pub fn check(&self) -> Vec<Mismatch> {
+        let mut mismatches = Vec::new();
+        let mut compare = |what: String, computed: f64, reported: f64, tolerance: f64| {
+            if (computed - reported).abs() > tolerance + 1e-9 {
+                mismatches.push(Mismatch {
+                    what,
+                    computed,
+                    reported,
+                });
+            }
+        };
+        for day in &self.days {
+            let computed = (BASE_TEMP - f64::from(day.avg_temp)).max(0.0);
+            let reported = day.heating_degree_days.unwrap_or(0).into();
+            compare(format!("day {} HDDay", day.day), computed, reported, 0.0);
+        }
+
+        let Some(summary) = &self.summary else {
+            return mismatches;
+        };
+        let average = |getter: Getter| mean(&self.values(getter));
+        let max_temp = average(|x| x.max_temp.into());
+        let min_temp = average(|x| x.min_temp.into());
+        let degree_days = self.degree_days(BASE_TEMP);
+        let totals = [
+            ("MxT", max_temp, summary.max_temp, 0.05),
+            ("MnT", min_temp, summary.min_temp, 0.05),
+            ("AvT", (max_temp + min_temp) / 2.0, summary.avg_temp, 0.05),
+            (
+                "HDDay",
+                degree_days.heating,
+                summary.heating_degree_days,
+                0.0,
+            ),
+            (
+                "AvDP",
+                average(|x| x.avg_dew_point),
+                summary.avg_dew_point,
+                0.05,
+            ),
+            (
+                "TPcpn",
+                self.values(|x| x.total_precip).iter().sum(),
+                summary.total_precip,
+                0.005,
+            ),
+            (
+                "AvSp",
+                average(|x| x.avg_wind_speed),
+                summary.avg_wind_speed,
+                0.05,
+            ),
+            ("SkyC", average(|x| x.sky_cover), summary.sky_cover, 0.05),
+        ];
+        for (name, computed, reported, tolerance) in totals {
+            compare(format!("mo {}", name), computed, reported, tolerance);
+        }
+        mismatches
+    }
+}
+
+//////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+use crate::common::DATA_DIR;
+
+#[cfg(test)]
+fn june() -> Month {
+    let table = Table::from_file(&DATA_DIR.join("weather.dat")).unwrap();
+    Month::from_table(&table).unwrap()
+}
+
+#[test]
+fn test_full_rows() {
+    for extension in ["dat", "csv", "tsv", "jsonl"] {
+        let table = Table::from_file(&DATA_DIR.join("weather").with_extension(extension)).unwrap();
+        let month = Month::from_table(&table).unwrap();
+        assert_eq!(month.days.len(), 30);
+        assert_eq!(month.days, june().days, "{}", extension);
+    }
+
+    let month = june();
+    let day = &month.days[8];
+    assert_eq!(
+        (day.day, day.max_temp, day.min_temp, day.avg_temp),
+        (9, 86, 32, 59)
+    );
+    assert_eq!(day.heating_degree_days, Some(6));
+    assert_eq!(
+        (day.avg_dew_point, day.hour_precip, day.total_precip),
+        (61.5, None, 0.0)
+    );
+    assert_eq!(day.weather, None);
+    assert_eq!(
+        (
+            day.peak_wind_dir,
+            day.avg_wind_speed,
+            day.wind_dir,
+            day.max_wind_speed
+        ),
+        (240, 7.6, 220, 12)
+    );
+    assert_eq!(
+        (
+            day.sky_cover,
+            day.max_humidity,
+            day.min_humidity,
+            day.pressure
+        ),
+        (6.0, 78, 46, 1018.6)
+    );
+    assert_eq!(day.flagged, ["MnT"]);
+    assert_eq!(month.days[27].weather.as_deref(), Some("RTFH"));
+
+    let flagged: Vec<_> = month
+        .days
+        .iter()
+        .filter(|x| !x.flagged.is_empty())
+        .map(|x| (x.day, x.flagged.clone()))
+        .collect();
+    assert_eq!(
+        flagged,
+        [
+            (9, vec!["MnT".to_string()]),
+            (17, vec!["MxS".to_string()]),
+            (26, vec!["MxT".to_string()])
+        ]
+    );
+
+    let summary = month.summary.unwrap();
+    assert_eq!(
+        (summary.max_temp, summary.min_temp, summary.avg_temp),
+        (82.9, 60.5, 71.7)
+    );
+    assert_eq!(
+        (summary.heating_degree_days, summary.sky_cover),
+        (16.0, 5.3)
+    );
+}
+
+#[test]
+fn test_degree_days() {
+    let month = june();
+    assert_eq!(
+        month.degree_days(BASE_TEMP),
+        DegreeDays {
+            heating: 16.0,
+            cooling: 223.0
+        }
+    );
+    assert_eq!(month.degree_days(60.0).heating, 1.0);
+    assert_eq!(month.check(), []);
+
+    // A wrong day and a wrong total are both reported
+    let mut month = june();
+    month.days[0].heating_degree_days = Some(2);
+    month.summary.as_mut().unwrap().avg_temp = 71.9;
+    let mismatches: Vec<String> = month.check().iter().map(|x| x.to_string()).collect();
+    assert_eq!(
+        mismatches,
+        [
+            "day 1 HDDay: reported 2, computed 0",
+            "mo AvT: reported 71.9, computed 71.71666666666667"
+        ]
+    );
+
+    month.summary = None;
+    assert_eq!(month.check().len(), 1);
+}
+
+#[test]
+fn test_rolling_windows() {
+    let month = june();
+    let weeks = month.rolling(column("MxT").unwrap(), 7);
+    assert_eq!(weeks.len(), 24);
+    assert_eq!((weeks[0].first_day, weeks[0].last_day), (1, 7));
+    assert_eq!(weeks[0].mean, 565.0 / 7.0);
+    assert_eq!((weeks[0].min, weeks[0].max), (73.0, 90.0));
+
+    let warmest = weeks
+        .iter()
+        .max_by(|a, b| a.mean.total_cmp(&b.mean))
+        .unwrap();
+    assert_eq!((warmest.first_day, warmest.last_day), (21, 27));
+
+    assert_eq!(month.rolling(column("AvT").unwrap(), 30).len(), 1);
+    assert_eq!(month.rolling(column("AvT").unwrap(), 31), []);
+    assert_eq!(month.rolling(column("AvT").unwrap(), 0), []);
+    assert!(column("Dy").is_none());
+}
+
+#[test]
+fn test_anomalies() {
+    let anomalies: Vec<_> = june()
+        .anomalies(3.0)
+        .iter()
+        .map(|x| (x.day, x.column, x.value))
+        .collect();
+    assert_eq!(
+        anomalies,
+        [
+            (30, "MxR", 200.0),
+            (9, "HDDay", 6.0),
+            (9, "MnT", 32.0),
+            (14, "MnR", 87.0),
+            (17, "MxS", 29.0)
+        ]
+    );
+    let anomalies = june().anomalies(2.5);
+    assert_eq!(anomalies.len(), 10);
+    assert!(anomalies.iter().all(|x| x.z_score.abs() >= 2.5));
+    assert_eq!(june().anomalies(5.0), []);
+}
@@ -1,44 +1,217 @@
-use crate::common::{WORDS, WORDS_DATA};
+mod blocked;
+mod cli;
+mod counting;
+mod cuckoo;
+mod error;
+mod file;
+mod hashers;
+mod membership;
+mod quotient;
+mod scalable;
+mod spell;
+mod xor;
+
+use crate::common::{XorShift, WORDS, WORDS_DATA};
+use blocked::BlockedFilter;
+pub use cli::bloom;
+use counting::CountingFilter;
+use cuckoo::CuckooFilter;
+use error::BloomError;
+use hashers::{Fnv1a, HashPair, HashType, Murmur3, SipHash24, WordHasher, XxHash64};
+use membership::Membership;
+use quotient::QuotientFilter;
+use scalable::ScalableFilter;
+pub use spell::spell;
+use xor::XorFilter;
 
 use bit_vec::BitVec;
-use std::collections::hash_map::DefaultHasher;
-use std::hash::{Hash, Hasher};
 
 //////////////////////////////////////////////////////////////////////////////
 
-type HashType = u64;
+fn get_hashes(hasher: &impl WordHasher, words: &str) -> Vec<HashPair> {
+    words
+        .lines()
+        .map(|x| hasher.hash_pair(x.as_bytes()))
+        .collect()
+}
+
+// Size for n items at the false positive rate p: m = -n ln p / (ln 2)^2.
+// Every filter sized by the rate is made through here, a rate of 0 would
+// ask for infinite bits and a NaN for none.
+fn optimal_bits(items: usize, fpr: f64) -> usize {
+    assert!(
+        0.0 < fpr && fpr < 1.0,
+        "false positive rate must be between 0 and 1, not {}",
+        fpr
+    );
+    let bits = -(items.max(1) as f64) * fpr.ln() / (2f64.ln() * 2f64.ln());
+    (bits.ceil() as usize).max(1)
+}
 
-fn calc_hash(word: &str) -> HashType {
-    let mut hasher = DefaultHasher::new();
-    word.hash(&mut hasher);
-    hasher.finish()
+// k = m / n ln 2 gives the lowest false positive rate for m bits
+fn optimal_hash_count(bits: usize, items: usize) -> u32 {
+    let count = bits as f64 / items.max(1) as f64 * 2f64.ln();
+    (count.round() as u32).max(1)
 }
 
-fn get_hashes(words: &str) -> Vec<HashType> {
-    words.lines().map(calc_hash).collect()
+// Kirsch-Mitzenmacher double hashing: g_i = h1 + i * h2 mod m
+fn offsets((first, second): HashPair, size: usize, hash_count: u32) -> impl Iterator<Item = usize> {
+    let size = size as HashType;
+    (0..hash_count as HashType)
+        .map(move |i| (first.wrapping_add(i.wrapping_mul(second)) % size) as usize)
 }
 
-struct BloomFilter {
+// The hashes given to the constructors must come from the same hasher
+struct BloomFilter<H: WordHasher = Murmur3> {
     bools: BitVec,
+    hash_count: u32,
+    hasher: H,
+    items: usize,
 }
 
-impl BloomFilter {
-    fn new(bits_size: usize, hashes: &[HashType]) -> BloomFilter {
-        let mut bools = BitVec::from_elem(bits_size, false);
+impl<H: WordHasher> BloomFilter<H> {
+    // Sized for the expected number of items and the false positive rate
+    fn new(items: usize, fpr: f64, hasher: H, hashes: &[HashPair]) -> BloomFilter<H> {
+        let bits = optimal_bits(items, fpr);
+        BloomFilter::with_size(bits, optimal_hash_count(bits, items), hasher, hashes)
+    }
+
+    fn with_size(
+        bits_size: usize,
+        hash_count: u32,
+        hasher: H,
+        hashes: &[HashPair],
+    ) -> BloomFilter<H> {
+        let mut filter = BloomFilter {
+            bools: BitVec::from_elem(bits_size, false),
+            hash_count,
+            hasher,
+            items: 0,
+        };
         for hash in hashes {
-            let offset = *hash as usize % bits_size;
-            bools.set(offset, true);
+            filter.insert_hash(*hash);
+        }
+        filter
+    }
+
+    fn insert_hash(&mut self, hash: HashPair) {
+        for offset in self.offsets(hash) {
+            self.bools.set(offset, true);
         }
-        BloomFilter { bools }
+        self.items += 1;
+    }
+
+    fn insert(&mut self, word: &str) {
+        self.insert_hash(self.hasher.hash_pair(word.as_bytes()));
+    }
+
+    fn estimated_items(&self) -> f64 {
+        estimated_items(&self.bools, self.hash_count)
+    }
+
+    // Like 'new' for the hashes of the words, with the hashing and the
+    // setting of the bits shared by the threads. Every thread makes a
+    // filter of its chunk of the words, OR-ing them together gives the
+    // same bits as setting them in one.
+    fn new_parallel(fpr: f64, hasher: H, words: &[&str], threads: usize) -> BloomFilter<H>
+    where
+        H: Clone + Send + Sync,
+    {
+        let bits = optimal_bits(words.len(), fpr);
+        let hash_count = optimal_hash_count(bits, words.len());
+        let chunk = words.len().div_ceil(threads.max(1)).max(1);
+        let partials: Vec<BloomFilter<H>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (words.chunks(chunk))
+                .map(|words| {
+                    let hasher = hasher.clone();
+                    scope.spawn(move || {
+                        let hashes: Vec<HashPair> = words
+                            .iter()
+                            .map(|x| hasher.hash_pair(x.as_bytes()))
+                            .collect();
+                        BloomFilter::with_size(bits, hash_count, hasher, &hashes)
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|x| x.join().unwrap()).collect()
+        });
+
+        let mut filter = BloomFilter::with_size(bits, hash_count, hasher, &[]);
+        for partial in partials {
+            filter.bools.or(&partial.bools);
+            filter.items += partial.items;
+        }
+        filter
+    }
+
+    fn offsets(&self, hash: HashPair) -> impl Iterator<Item = usize> {
+        offsets(hash, self.bools.len(), self.hash_count)
     }
 
-    #[cfg(test)]
     fn contains(&self, word: &str) -> bool {
-        let offset = calc_hash(word) as usize % self.bools.len();
-        self.bools.get(offset).unwrap()
+        self.offsets(self.hasher.hash_pair(word.as_bytes()))
+            .all(|offset| self.bools.get(offset).unwrap())
     }
 }
 
+// Adding many words and combining filters, like the ones built on shards
+impl<H: WordHasher> BloomFilter<H> {
+    fn insert_all<'a>(&mut self, words: impl IntoIterator<Item = &'a str>) {
+        for word in words {
+            self.insert(word);
+        }
+    }
+
+    // Only filters of the same size and hashing can be combined
+    fn check_compatible(&self, other: &BloomFilter<H>) -> Result<(), BloomError> {
+        let mismatch =
+            |field, left: u128, right: u128| Err(BloomError::Mismatch { field, left, right });
+        if self.bools.len() != other.bools.len() {
+            return mismatch("bits", self.bools.len() as u128, other.bools.len() as u128);
+        }
+        if self.hash_count != other.hash_count {
+            return mismatch("hashes", self.hash_count.into(), other.hash_count.into());
+        }
+        if self.hasher.id() != other.hasher.id() {
+            return mismatch("hasher", self.hasher.id().into(), other.hasher.id().into());
+        }
+        if self.hasher.key() != other.hasher.key() {
+            return mismatch("hasher key", self.hasher.key(), other.hasher.key());
+        }
+        Ok(())
+    }
+
+    // Has the words of both, as if built from all of them
+    fn union(&mut self, other: &BloomFilter<H>) -> Result<(), BloomError> {
+        self.check_compatible(other)?;
+        self.bools.or(&other.bools);
+        self.items = self.estimated_items().round() as usize;
+        Ok(())
+    }
+
+    // Has the common words, with a false positive rate up to the one of each.
+    // The bits left by the other words overestimate the count, the estimate
+    // of both minus the one of the union is closer
+    fn intersection(&mut self, other: &BloomFilter<H>) -> Result<(), BloomError> {
+        self.check_compatible(other)?;
+        let mut union = self.bools.clone();
+        union.or(&other.bools);
+        let common = self.estimated_items() + other.estimated_items()
+            - estimated_items(&union, self.hash_count);
+        self.bools.and(&other.bools);
+        self.items = common.max(0.0).round() as usize;
+        Ok(())
+    }
+}
+
+// Swamidass and Baldi: n = -m / k ln(1 - X / m) for X bits set,
+// infinite when all the bits are set
+fn estimated_items(bools: &BitVec, hash_count: u32) -> f64 {
+    let bits = bools.len() as f64;
+    let set = bits_count(bools) as f64;
+    -bits / hash_count as f64 * (1.0 - set / bits).ln()
+}
+
 //////////////////////////////////////////////////////////////////////////////
 
 #[cfg(test)]
@@ -47,22 +220,76 @@ const RIGHT_WORDS: [&str; 5] = ["basic", "forum", "tree", "will", "power"];
 const WRONG_WORDS: [&str; 5] = ["baaac", "fooom", "teee", "wiil", "pooor"];
 
 fn bits_count(bits: &BitVec) -> usize {
-    let mut count = 1;
+    let mut count = 0;
     for block in bits.blocks() {
         count += block.count_ones();
     }
     count as usize
 }
 
+// Random lowercase words of 3 to 10 letters which are not in the word list
+fn non_words(count: usize, seed: u64) -> Vec<String> {
+    let mut random = XorShift::new(seed);
+    let mut words = Vec::with_capacity(count);
+    while words.len() < count {
+        let len = 3 + random.below(8);
+        let word: String = (0..len)
+            .map(|_| (b'a' + random.below(26) as u8) as char)
+            .collect();
+        if !WORDS.contains(word.as_str()) {
+            words.push(word);
+        }
+    }
+    words
+}
+
+// (1 - e^(-kn/m))^k for n items in m bits with k hashes
+fn theoretical_fpr(bits: usize, hash_count: u32, items: usize) -> f64 {
+    let k = hash_count as f64;
+    (1.0 - (-k * items as f64 / bits as f64).exp()).powf(k)
+}
+
+// Share of the probes the filter wrongly says it has
+fn measured_fpr(filter: &BloomFilter<impl WordHasher>, non_words: &[String]) -> f64 {
+    let hits = non_words.iter().filter(|x| filter.contains(x)).count();
+    hits as f64 / non_words.len() as f64
+}
+
+struct Evaluation {
+    bits: usize,
+    hash_count: u32,
+    set_bits: usize,
+    measured: f64,
+    theoretical: f64,
+}
+
+// A filter with the optimal hash count of every size, built from all the words
+fn evaluate(word_hashes: &[HashPair], sizes: &[usize], probes: &[String]) -> Vec<Evaluation> {
+    sizes
+        .iter()
+        .map(|&bits| {
+            let hash_count = optimal_hash_count(bits, word_hashes.len());
+            let filter = BloomFilter::with_size(bits, hash_count, Murmur3::default(), word_hashes);
+            Evaluation {
+                bits,
+                hash_count,
+                set_bits: bits_count(&filter.bools),
+                measured: measured_fpr(&filter, probes),
+                theoretical: theoretical_fpr(bits, hash_count, word_hashes.len()),
+            }
+        })
+        .collect()
+}
+
 #[test]
 fn test_filter() {
-    let word_hashes = get_hashes(&WORDS_DATA);
+    let word_hashes = get_hashes(&Murmur3::default(), &WORDS_DATA);
 
-    let filter = BloomFilter::new(16 * 1024, &word_hashes);
+    let filter = BloomFilter::with_size(16 * 1024, 1, Murmur3::default(), &word_hashes);
     assert!(filter.contains(WRONG_WORDS[0])); // small size causes lots of collisions
     assert!(filter.contains(WRONG_WORDS[4])); // the same
 
-    let filter = BloomFilter::new(4024 * 1024, &word_hashes); // large enough to avoid collisions
+    let filter = BloomFilter::new(WORDS.len(), 0.001, Murmur3::default(), &word_hashes); // large enough to avoid collisions
     for word in RIGHT_WORDS {
         assert!(WORDS.contains(word), "{}", word);
         assert!(filter.contains(word), "{}", word);
@@ -73,6 +300,419 @@ fn test_filter() {
     }
 }
 
+#[test]
+fn test_optimal_parameters() {
+    assert_eq!(optimal_bits(1000, 0.01), 9586);
+    assert_eq!(optimal_hash_count(9586, 1000), 7);
+    assert_eq!(optimal_bits(1000, 0.001), 14378);
+    assert_eq!(optimal_hash_count(14378, 1000), 10);
+    assert_eq!(optimal_hash_count(1000, 1000), 1); // never zero hashes
+    assert_eq!(optimal_bits(0, 0.5), 2);
+
+    let filter = BloomFilter::new(1000, 0.01, Murmur3::default(), &[]);
+    assert_eq!((filter.bools.len(), filter.hash_count), (9586, 7));
+}
+
+#[test]
+fn test_bad_rates() {
+    let hasher = Murmur3::default();
+    for fpr in [0.0, 1.0, -0.5, f64::NAN] {
+        let constructors: [&(dyn Fn() + std::panic::RefUnwindSafe); 3] = [
+            &|| _ = BloomFilter::new(1000, fpr, hasher, &[]),
+            &|| _ = BlockedFilter::new(1000, fpr, hasher),
+            &|| _ = CountingFilter::new(1000, fpr, hasher),
+        ];
+        for constructor in constructors {
+            let panic = std::panic::catch_unwind(constructor).unwrap_err();
+            assert_eq!(
+                panic.downcast_ref::<String>().unwrap(),
+                &format!("false positive rate must be between 0 and 1, not {}", fpr)
+            );
+        }
+    }
+}
+
+#[test]
+fn test_false_positive_rate() {
+    fn check(hasher: impl WordHasher) {
+        let hashes = get_hashes(&hasher, &WORDS_DATA);
+        let filter = BloomFilter::new(WORDS.len(), 0.01, hasher, &hashes);
+        assert!(WORDS.iter().all(|word| filter.contains(word)));
+
+        // Digits are never in the word list, so every hit is a false positive
+        let probes = 100_000;
+        let hits = (0..probes)
+            .filter(|i| filter.contains(&format!("w{}", i)))
+            .count();
+        let rate = hits as f64 / probes as f64;
+        assert!(0.005 < rate && rate < 0.015, "{}", rate);
+    }
+
+    check(Fnv1a);
+    check(Murmur3::default());
+    check(XxHash64::default());
+    check(SipHash24::default());
+}
+
+#[test]
+fn test_bits_count() {
+    assert_eq!(bits_count(&BitVec::from_elem(100, false)), 0);
+    assert_eq!(bits_count(&BitVec::from_elem(100, true)), 100);
+    assert_eq!(bits_count(&BitVec::from_bytes(&[0b1010_0001, 0, 0xff])), 11);
+}
+
+#[test]
+fn test_measured_vs_theoretical() {
+    let probes = non_words(20_000, 5);
+    assert!(probes.iter().all(|x| !WORDS.contains(x.as_str())));
+    assert_eq!(probes, non_words(20_000, 5));
+
+    let sizes = [128 * 1024, 1024 * 1024, 4096 * 1024];
+    for row in evaluate(
+        &get_hashes(&Murmur3::default(), &WORDS_DATA),
+        &sizes,
+        &probes,
+    ) {
+        // Three standard deviations of the measurement, plus some slack
+        let p = row.theoretical;
+        let margin = 3.0 * (p * (1.0 - p) / probes.len() as f64).sqrt() + 0.002;
+        assert!(
+            (row.measured - p).abs() < margin,
+            "{} bits: {} vs {}",
+            row.bits,
+            row.measured,
+            p
+        );
+        assert!(row.set_bits < row.bits);
+    }
+
+    assert_eq!(theoretical_fpr(1000, 1, 0), 0.0);
+    assert!((theoretical_fpr(1000, 1, 1000) - (1.0 - (-1f64).exp())).abs() < 1e-12);
+    assert!((theoretical_fpr(9586, 7, 1000) - 0.01).abs() < 0.0005);
+}
+
+#[test]
+fn test_insert() {
+    let hasher = Murmur3::default();
+    let built = BloomFilter::with_size(
+        100_000,
+        5,
+        hasher,
+        &get_hashes(&hasher, &WORDS_DATA[..50_000]),
+    );
+    let mut filter = BloomFilter::with_size(100_000, 5, hasher, &[]);
+    assert!(!filter.contains("basic"));
+    filter.insert_all(WORDS_DATA[..50_000].lines());
+    assert_eq!(filter.bools, built.bools);
+    assert_eq!(filter.items, built.items);
+
+    filter.insert("basic");
+    assert!(filter.contains("basic"));
+    assert_eq!(filter.items, built.items + 1);
+}
+
+#[test]
+fn test_parallel_build() {
+    let words: Vec<&str> = WORDS_DATA.lines().collect();
+    let hasher = SipHash24 { key: (3, 4) };
+    let sequential = BloomFilter::new(words.len(), 0.01, hasher, &get_hashes(&hasher, &WORDS_DATA));
+    for threads in [1, 2, 3, 8] {
+        let parallel = BloomFilter::new_parallel(0.01, hasher, &words, threads);
+        assert_eq!(parallel.to_bytes(), sequential.to_bytes(), "{}", threads);
+    }
+
+    // More threads than words, and no words at all
+    let few = BloomFilter::new_parallel(0.01, hasher, &words[..3], 16);
+    let expected = BloomFilter::new(
+        3,
+        0.01,
+        hasher,
+        &get_hashes(&hasher, &words[..3].join("\n")),
+    );
+    assert_eq!(few.to_bytes(), expected.to_bytes());
+    let empty = BloomFilter::new_parallel(0.01, hasher, &[], 4);
+    assert_eq!(
+        empty.to_bytes(),
+        BloomFilter::new(0, 0.01, hasher, &[]).to_bytes()
+    );
+}
+
+#[test]
+fn test_union_and_intersection() {
+    // Shards of the words, a word of every tenth line is in both
+    let (mut first, mut second) = (Vec::new(), Vec::new());
+    for (i, word) in WORDS_DATA.lines().enumerate() {
+        match i % 10 {
+            0 => {
+                first.push(word);
+                second.push(word);
+            }
+            1..=5 => first.push(word),
+            _ => second.push(word),
+        }
+    }
+    let build = |words: &[&str]| {
+        let mut filter = BloomFilter::new(WORDS.len(), 0.01, Murmur3::default(), &[]);
+        filter.insert_all(words.iter().copied());
+        filter
+    };
+    let whole = build(&WORDS_DATA.lines().collect::<Vec<_>>());
+
+    let mut union = build(&first);
+    union.union(&build(&second)).unwrap();
+    assert_eq!(union.bools, whole.bools);
+    assert!(
+        (union.items as f64 / WORDS.len() as f64 - 1.0).abs() < 0.02,
+        "{}",
+        union.items
+    );
+
+    let mut intersection = build(&first);
+    intersection.intersection(&build(&second)).unwrap();
+    assert!(first.iter().step_by(6).all(|x| intersection.contains(x)));
+    let common = WORDS.len().div_ceil(10) as f64;
+    assert!(
+        (intersection.items as f64 / common - 1.0).abs() < 0.05,
+        "{}",
+        intersection.items
+    );
+    assert!(intersection.estimated_items() > common * 1.5); // the raw bits overestimate
+
+    let mut filter = BloomFilter::with_size(1000, 3, Murmur3::default(), &[]);
+    let mut error = |other: &BloomFilter<_>| filter.union(other).err().unwrap().to_string();
+    assert_eq!(
+        error(&BloomFilter::with_size(1001, 3, Murmur3::default(), &[])),
+        "filters differ in bits: 1000 and 1001"
+    );
+    assert_eq!(
+        error(&BloomFilter::with_size(1000, 4, Murmur3::default(), &[])),
+        "filters differ in hashes: 3 and 4"
+    );
+    assert_eq!(
+        error(&BloomFilter::with_size(1000, 3, Murmur3 { seed: 9 }, &[])),
+        "filters differ in hasher key: 0 and 9"
+    );
+    let mut fnv = BloomFilter::with_size(1000, 3, Fnv1a, &[]);
+    fnv.insert("a");
+    assert!(fnv
+        .intersection(&BloomFilter::with_size(1000, 3, Fnv1a, &[]))
+        .is_ok());
+    assert!(!fnv.contains("a"));
+}
+
+#[test]
+fn test_estimated_items() {
+    let mut filter = BloomFilter::new(10_000, 0.01, XxHash64::default(), &[]);
+    assert_eq!(filter.estimated_items(), 0.0);
+    for (i, word) in WORDS_DATA.lines().take(10_000).enumerate() {
+        filter.insert(word);
+        if (i + 1) % 2500 == 0 {
+            let error = filter.estimated_items() / (i + 1) as f64 - 1.0;
+            assert!(error.abs() < 0.03, "{} {}", i, error);
+        }
+    }
+    let full = BloomFilter::with_size(
+        10,
+        1,
+        XxHash64::default(),
+        &get_hashes(&XxHash64::default(), &WORDS_DATA[..10_000]),
+    );
+    assert_eq!(full.estimated_items(), f64::INFINITY);
+}
+
+// A counting filter after removing every tenth word
+fn print_counting_stats(probes: &[String]) {
+    let mut filter = CountingFilter::new(WORDS.len(), 0.01, Murmur3::default());
+    for word in WORDS_DATA.lines() {
+        filter.insert(word);
+    }
+    for word in WORDS_DATA.lines().step_by(10) {
+        filter.remove(word);
+    }
+    let hits = probes.iter().filter(|x| filter.contains(x)).count();
+    println!(
+        "Counting filter: {} bytes, {} items, {} saturated, false positives {:7.3}%",
+        filter.memory(),
+        filter.len(),
+        filter.saturated(),
+        hits as f64 * 100.0 / probes.len() as f64
+    );
+}
+
+// A scalable filter for all the words, starting as small as for 1000 of them
+fn print_scalable_stats(probes: &[String]) {
+    let mut filter = ScalableFilter::new(1000, 0.01, Murmur3::default());
+    for word in WORDS_DATA.lines() {
+        filter.insert(word);
+    }
+    let hits = probes.iter().filter(|x| filter.contains(x)).count();
+    println!(
+        "Scalable filter: {} filters, {} bits, {} items, false positives {:7.3}%, expected {:.3}%",
+        filter.filter_count(),
+        filter.bits(),
+        filter.len(),
+        hits as f64 * 100.0 / probes.len() as f64,
+        filter.expected_fpr() * 100.0
+    );
+}
+
+// Built from all the words, at about 1% false positives where it can be chosen
+fn print_comparison(probes: &[String]) {
+    fn compare(build: impl FnOnce() -> Box<dyn Membership>, probes: &[String]) {
+        let now = std::time::Instant::now();
+        let filter = build();
+        let build_time = now.elapsed();
+        let now = std::time::Instant::now();
+        let hits = probes.iter().filter(|x| filter.contains(x)).count();
+        let lookup_time = now.elapsed() / probes.len() as u32;
+        println!(
+            "{:10} {:9.2} {:>10.2?} {:>10.2?} {:9.3}%",
+            filter.name(),
+            filter.bits() as f64 / WORDS.len() as f64,
+            build_time,
+            lookup_time,
+            hits as f64 * 100.0 / probes.len() as f64
+        );
+    }
+
+    let words = || WORDS_DATA.lines();
+    println!(
+        "{:10} {:>9} {:>10} {:>10} {:>10}",
+        "Filter", "bits/item", "build", "lookup", "false pos."
+    );
+    compare(
+        || {
+            let hasher = Murmur3::default();
+            let hashes = get_hashes(&hasher, &WORDS_DATA);
+            Box::new(BloomFilter::new(WORDS.len(), 0.01, hasher, &hashes))
+        },
+        probes,
+    );
+    compare(
+        || {
+            let mut filter = CountingFilter::new(WORDS.len(), 0.01, Murmur3::default());
+            words().for_each(|x| filter.insert(x));
+            Box::new(filter)
+        },
+        probes,
+    );
+    compare(
+        || {
+            let mut filter = ScalableFilter::new(1000, 0.01, Murmur3::default());
+            words().for_each(|x| {
+                filter.insert(x);
+            });
+            Box::new(filter)
+        },
+        probes,
+    );
+    compare(
+        || {
+            let mut filter = CuckooFilter::new(WORDS.len(), Murmur3::default());
+            words().for_each(|x| {
+                filter.insert(x);
+            });
+            Box::new(filter)
+        },
+        probes,
+    );
+    compare(
+        || {
+            let mut filter = QuotientFilter::new(WORDS.len(), 0.01, Murmur3::default());
+            words().for_each(|x| {
+                filter.insert(x);
+            });
+            Box::new(filter)
+        },
+        probes,
+    );
+    compare(
+        || {
+            let mut filter = BlockedFilter::new(WORDS.len(), 0.01, Murmur3::default());
+            words().for_each(|x| filter.insert(x));
+            Box::new(filter)
+        },
+        probes,
+    );
+    compare(
+        || Box::new(XorFilter::new(words(), Murmur3::default())),
+        probes,
+    );
+}
+
+// Lookups of all the words and the probes, the best of 5 runs against cache
+// and frequency warm-up, in a standard and in a blocked filter of the same size
+fn print_blocked_benchmark(probes: &[String]) {
+    let hasher = Murmur3::default();
+    let standard = BloomFilter::new(WORDS.len(), 0.01, hasher, &get_hashes(&hasher, &WORDS_DATA));
+    let mut blocked = BlockedFilter::new(WORDS.len(), 0.01, hasher);
+    WORDS_DATA.lines().for_each(|x| blocked.insert(x));
+
+    let filters: [&dyn Membership; 2] = [&standard, &blocked];
+    for filter in filters {
+        let lookups = WORDS.len() + probes.len();
+        let best = (0..5)
+            .map(|_| {
+                let now = std::time::Instant::now();
+                let found = WORDS_DATA.lines().filter(|x| filter.contains(x)).count();
+                let hits = probes.iter().filter(|x| filter.contains(x)).count();
+                (now.elapsed(), hits, found)
+            })
+            .min()
+            .unwrap();
+        if best.2 != WORDS.len() {
+            println!(
+                "{} found {} of {} words",
+                filter.name(),
+                best.2,
+                WORDS.len()
+            );
+        }
+        println!(
+            "{:8} lookups: {:>9.2?}, {:>7.2?} each, false positives {:7.3}%",
+            filter.name(),
+            best.0,
+            best.0 / lookups as u32,
+            best.1 as f64 * 100.0 / probes.len() as f64
+        );
+    }
+}
+
+// Hashing and building from all the words on one thread and on all of them
+fn print_parallel_stats() {
+    let words: Vec<&str> = WORDS_DATA.lines().collect();
+    let threads = std::thread::available_parallelism().map_or(1, |x| x.get());
+    let now = std::time::Instant::now();
+    let hasher = Murmur3::default();
+    let sequential = BloomFilter::new(words.len(), 0.01, hasher, &get_hashes(&hasher, &WORDS_DATA));
+    let sequential_time = now.elapsed();
+    let now = std::time::Instant::now();
+    let parallel = BloomFilter::new_parallel(0.01, hasher, &words, threads);
+    let parallel_time = now.elapsed();
+    if parallel.bools != sequential.bools {
+        println!("Parallel build differs from the sequential one");
+    }
+    println!(
+        "Build time: sequential {:.2?}, parallel {:.2?} on {} threads",
+        sequential_time, parallel_time, threads
+    );
+}
+
+// Hashing time of all the words and the false positives at 1%
+fn print_hasher_stats(hasher: impl WordHasher, probes: &[String]) {
+    let now = std::time::Instant::now();
+    let hashes = get_hashes(&hasher, &WORDS_DATA);
+    let elapsed = now.elapsed();
+    let name = hasher.name();
+    let filter = BloomFilter::new(WORDS.len(), 0.01, hasher, &hashes);
+    println!(
+        "Hasher {:12} hashing {:>9.2?}, false positives {:7.3}%",
+        name,
+        elapsed,
+        measured_fpr(&filter, probes) * 100.0
+    );
+}
+
 pub fn print_stats() {
     let now = std::time::Instant::now();
     println!("Kata5: Bloom Filter");
@@ -82,17 +722,46 @@ pub fn print_stats() {
         WORDS.iter().max_by_key(|w| w.len()).unwrap()
     );
 
-    let word_hashes = get_hashes(&WORDS_DATA);
-    let sizes = [128, 256, 512, 1024, 2048, 4096, 10240];
-    for size in sizes {
-        let filter = BloomFilter::new(size * 1024, &word_hashes);
-        let len = filter.bools.len();
-        let bits = bits_count(&filter.bools);
-        let colls = (WORDS.len() - bits) * 100 / bits;
+    let word_hashes = get_hashes(&Murmur3::default(), &WORDS_DATA);
+    let sizes = [128, 256, 512, 1024, 2048, 4096, 10240].map(|x| x * 1024);
+    let probes = non_words(100_000, 1);
+    for row in evaluate(&word_hashes, &sizes, &probes) {
         println!(
-            "Bits: total {:8}, set {:6}, collisions {}%",
-            len, bits, colls
+            "Bits: total {:8}, hashes {:2}, filled {:2}%, false positives {:7.3}%, in theory {:7.3}%",
+            row.bits,
+            row.hash_count,
+            row.set_bits * 100 / row.bits,
+            row.measured * 100.0,
+            row.theoretical * 100.0
         );
     }
+    let filter = BloomFilter::new(WORDS.len(), 0.01, Murmur3::default(), &word_hashes);
+    println!(
+        "Bits for 1% false positives: total {:8}, hashes {:2}, estimated items {:.0}",
+        filter.bools.len(),
+        filter.hash_count,
+        filter.estimated_items()
+    );
+    let saving = std::time::Instant::now();
+    let bytes = filter.to_bytes();
+    match BloomFilter::from_bytes(&bytes, Murmur3::default()) {
+        Ok(x) if x.bools == filter.bools && x.items == filter.items => (),
+        Ok(_) => println!("Loaded filter differs from the saved one"),
+        Err(error) => println!("Loading the saved filter failed: {}", error),
+    }
+    println!(
+        "Saved filter: {} bytes, saved and loaded in {:.2?}",
+        bytes.len(),
+        saving.elapsed()
+    );
+    print_counting_stats(&probes);
+    print_scalable_stats(&probes);
+    print_comparison(&probes);
+    print_blocked_benchmark(&probes);
+    print_parallel_stats();
+    print_hasher_stats(Fnv1a, &probes);
+    print_hasher_stats(Murmur3::default(), &probes);
+    print_hasher_stats(XxHash64::default(), &probes);
+    print_hasher_stats(SipHash24::default(), &probes);
     println!("Elapsed: {:.2?}", now.elapsed());
 }
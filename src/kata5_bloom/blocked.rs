@@ -0,0 +1,92 @@
+// Blocked Bloom filter (Putze et al.): the first hash picks a 64-byte block,
+// a cache line, and all the k bits of a word are in it. A lookup is a single
+// cache miss instead of k, for a higher false positive rate: some blocks get
+// more words than the average, and their bits fill up.
+
+use super::hashers::{HashPair, Murmur3, WordHasher};
+use super::membership::Membership;
+use super::{optimal_bits, optimal_hash_count};
+
+const BLOCK_BITS: usize = 512;
+
+#[derive(Copy, Clone, Default)]
+#[repr(align(64))]
+struct Block([u64; BLOCK_BITS / 64]);
+
+pub struct BlockedFilter<H: WordHasher = Murmur3> {
+    blocks: Vec<Block>,
+    hash_count: u32,
+    hasher: H,
+}
+
+impl<H: WordHasher> BlockedFilter<H> {
+    // Sized like 'BloomFilter::new', rounded up to whole blocks
+    pub fn new(items: usize, fpr: f64, hasher: H) -> BlockedFilter<H> {
+        let bits = optimal_bits(items, fpr);
+        BlockedFilter {
+            blocks: vec![Block::default(); bits.div_ceil(BLOCK_BITS)],
+            hash_count: optimal_hash_count(bits, items),
+            hasher,
+        }
+    }
+
+    // The block from the high bits of the first hash scaled to the number of
+    // blocks, the bits by double hashing the halves of the second one
+    fn locate(&self, (first, second): HashPair) -> (usize, impl Iterator<Item = usize>) {
+        let block = ((first as u128 * self.blocks.len() as u128) >> 64) as usize;
+        let (low, high) = (second as u32, (second >> 32) as u32 | 1);
+        let bits = (0..self.hash_count)
+            .map(move |i| low.wrapping_add(i.wrapping_mul(high)) as usize % BLOCK_BITS);
+        (block, bits)
+    }
+
+    pub fn insert(&mut self, word: &str) {
+        let (block, bits) = self.locate(self.hasher.hash_pair(word.as_bytes()));
+        let words = &mut self.blocks[block].0;
+        for bit in bits {
+            words[bit / 64] |= 1 << (bit % 64);
+        }
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        let (block, mut bits) = self.locate(self.hasher.hash_pair(word.as_bytes()));
+        let words = &self.blocks[block].0;
+        bits.all(|bit| words[bit / 64] & (1 << (bit % 64)) != 0)
+    }
+}
+
+impl<H: WordHasher> Membership for BlockedFilter<H> {
+    fn name(&self) -> &'static str {
+        "Blocked"
+    }
+
+    fn contains(&self, word: &str) -> bool {
+        BlockedFilter::contains(self, word)
+    }
+
+    fn bits(&self) -> usize {
+        self.blocks.len() * BLOCK_BITS
+    }
+}
+
+//////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+use super::non_words;
+#[cfg(test)]
+use crate::common::{WORDS, WORDS_DATA};
+
+#[test]
+fn test_blocked() {
+    let mut filter = BlockedFilter::new(WORDS.len(), 0.01, Murmur3::default());
+    assert_eq!(std::mem::align_of_val(&filter.blocks[0]), 64);
+    assert_eq!(filter.blocks.as_ptr() as usize % 64, 0);
+    assert_eq!(filter.blocks.len(), 3248204usize.div_ceil(512));
+    WORDS_DATA.lines().for_each(|x| filter.insert(x));
+    assert!(WORDS.iter().all(|x| filter.contains(x)));
+
+    // The rate is higher than the 1% of the standard filter of the same size
+    let probes = non_words(100_000, 23);
+    let rate = probes.iter().filter(|x| filter.contains(x)).count() as f64 / 1e5;
+    assert!(0.0115 < rate && rate < 0.016, "{}", rate);
+}
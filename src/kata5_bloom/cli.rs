@@ -0,0 +1,393 @@
+use super::hashers::{AnyHasher, WordHasher};
+use super::{bits_count, BloomFilter};
+use crate::cli::Args;
+
+use std::io::{BufRead, Write};
+use std::path::Path;
+use std::process::ExitCode;
+
+pub const USAGE: &str = "\
+Usage: codekata bloom build --input FILE [--fpr P] [--hasher NAME] --out FILE
+       codekata bloom add FILE [WORD...]
+       codekata bloom merge FILE FILE... [--intersect] --out FILE
+       codekata bloom query FILE [--quiet] [WORD...]
+       codekata bloom info FILE
+
+build  makes a filter of the words of FILE, one a line, at the false positive
+       rate P, 0.01 by default. NAME is fnv1a, murmur3, xxh64 or siphash,
+       murmur3 by default. FILE '-' is the standard input or output.
+add    inserts every WORD into the filter FILE. Without WORDs, reads them from
+       the standard input, one a line.
+merge  combines filters of the same size and hashing, like the ones built on
+       shards of the words. The result has the words of any of them, or with
+       --intersect only the words of all of them.
+query  prints every WORD with 'maybe' if the filter may have it, 'no' if not,
+       and fails if any is not there. Without WORDs, reads them from the
+       standard input, one a line. --quiet prints nothing.
+info   prints the size, the hashing and the estimates of the filter.";
+
+// A rate between 0 and 1, the default without one
+pub fn parse_fpr(text: Option<&str>, default: f64) -> Result<f64, String> {
+    match text {
+        Some(text) => text
+            .parse::<f64>()
+            .ok()
+            .filter(|x| 0.0 < *x && *x < 1.0)
+            .ok_or(format!("bad false positive rate '{}'", text)),
+        None => Ok(default),
+    }
+}
+
+fn read_input(filename: &str, input: &mut dyn BufRead) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    match filename {
+        "-" => input.read_to_end(&mut bytes).map(|_| bytes),
+        _ => std::fs::read(filename),
+    }
+    .map_err(|x| format!("{}: {}", filename, x))
+}
+
+fn load(filename: &str) -> Result<BloomFilter<AnyHasher>, String> {
+    let bytes = std::fs::read(filename).map_err(|x| format!("{}: {}", filename, x))?;
+    BloomFilter::from_bytes_any(&bytes).map_err(|x| format!("{}: {}", filename, x))
+}
+
+// To a file or to the standard output for '-'
+fn write_filter<H: WordHasher>(
+    filter: &BloomFilter<H>,
+    filename: &str,
+    out: &mut dyn Write,
+) -> Result<(), String> {
+    let written = match filename {
+        "-" => out.write_all(&filter.to_bytes()).map_err(|x| x.to_string()),
+        _ => filter.save(Path::new(filename)).map_err(|x| x.to_string()),
+    };
+    written.map_err(|x| format!("{}: {}", filename, x))
+}
+
+// The words of the command line, or the ones of the standard input kept in 'buffer'
+fn words_or_input<'a>(
+    words: &'a [String],
+    input: &mut dyn BufRead,
+    buffer: &'a mut Vec<u8>,
+) -> Result<Vec<&'a str>, String> {
+    if !words.is_empty() {
+        return Ok(words.iter().map(String::as_str).collect());
+    }
+    input.read_to_end(buffer).map_err(|x| x.to_string())?;
+    Ok(std::str::from_utf8(buffer)
+        .map_err(|_| "standard input: not UTF-8".to_string())?
+        .lines()
+        .filter(|x| !x.is_empty())
+        .collect())
+}
+
+fn build(
+    args: &Args,
+    input: &mut dyn BufRead,
+    out: &mut dyn Write,
+    err: &mut dyn Write,
+) -> Result<ExitCode, String> {
+    let (Some(input_name), Some(output_name)) = (args.option("input"), args.option("out")) else {
+        return Err(USAGE.to_string());
+    };
+    let fpr = parse_fpr(args.option("fpr"), 0.01)?;
+    let hasher_name = args.option("hasher").unwrap_or("murmur3");
+    let hasher = AnyHasher::from_name(hasher_name).ok_or(format!(
+        "unknown hasher '{}', one of {}",
+        hasher_name,
+        AnyHasher::NAMES.join(", ")
+    ))?;
+
+    let bytes = read_input(input_name, input)?;
+    let text = String::from_utf8(bytes).map_err(|_| format!("{}: not UTF-8", input_name))?;
+    let words: Vec<&str> = text.lines().filter(|x| !x.is_empty()).collect();
+    let threads = std::thread::available_parallelism().map_or(1, |x| x.get());
+    let filter = BloomFilter::new_parallel(fpr, hasher, &words, threads);
+
+    write_filter(&filter, output_name, out)?;
+    writeln!(
+        err,
+        "{} words, {} bits, {} hashes",
+        words.len(),
+        filter.bools.len(),
+        filter.hash_count
+    )
+    .map_err(|x| x.to_string())?;
+    Ok(ExitCode::SUCCESS)
+}
+
+fn add(args: &Args, input: &mut dyn BufRead, err: &mut dyn Write) -> Result<ExitCode, String> {
+    let [_, filename, words @ ..] = args.positional() else {
+        return Err(USAGE.to_string());
+    };
+    let mut filter = load(filename)?;
+    let mut buffer = Vec::new();
+    let words = words_or_input(words, input, &mut buffer)?;
+    filter.insert_all(words.iter().copied());
+    filter
+        .save(Path::new(filename))
+        .map_err(|x| format!("{}: {}", filename, x))?;
+    writeln!(err, "{} words added, {} items", words.len(), filter.items)
+        .map_err(|x| x.to_string())?;
+    Ok(ExitCode::SUCCESS)
+}
+
+// The filters are checked against the first one as they are combined
+fn merge(args: &Args, out: &mut dyn Write, err: &mut dyn Write) -> Result<ExitCode, String> {
+    let ([_, first, others @ ..], Some(output_name)) = (args.positional(), args.option("out"))
+    else {
+        return Err(USAGE.to_string());
+    };
+    if others.is_empty() {
+        return Err(USAGE.to_string());
+    }
+    let mut filter = load(first)?;
+    for filename in others {
+        let other = load(filename)?;
+        match args.flag("intersect") {
+            true => filter.intersection(&other),
+            false => filter.union(&other),
+        }
+        .map_err(|x| format!("{}: {}", filename, x))?;
+    }
+    write_filter(&filter, output_name, out)?;
+    writeln!(
+        err,
+        "{} items, {} bits, {} hashes",
+        filter.items,
+        filter.bools.len(),
+        filter.hash_count
+    )
+    .map_err(|x| x.to_string())?;
+    Ok(ExitCode::SUCCESS)
+}
+
+fn query(args: &Args, input: &mut dyn BufRead, out: &mut dyn Write) -> Result<ExitCode, String> {
+    let [_, filename, words @ ..] = args.positional() else {
+        return Err(USAGE.to_string());
+    };
+    let filter = load(filename)?;
+    let mut buffer = Vec::new();
+    let words = words_or_input(words, input, &mut buffer)?;
+
+    let mut all = true;
+    for word in words {
+        let found = filter.contains(word);
+        all &= found;
+        if !args.flag("quiet") {
+            let answer = if found { "maybe" } else { "no" };
+            writeln!(out, "{} {}", word, answer).map_err(|x| x.to_string())?;
+        }
+    }
+    Ok(if all {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+fn info(args: &Args, out: &mut dyn Write) -> Result<ExitCode, String> {
+    let [_, filename] = args.positional() else {
+        return Err(USAGE.to_string());
+    };
+    let filter = load(filename)?;
+    let bits = filter.bools.len();
+    let fill = bits_count(&filter.bools) as f64 / bits as f64;
+    // No set bits gives -0, which max keeps, all of them infinity
+    let estimate = match filter.estimated_items() {
+        x if x.is_infinite() => "all bits set".to_string(),
+        x => format!("{:.0}", x.max(0.0).abs()),
+    };
+    let text = format!(
+        "bits (m):          {}\n\
+         hashes (k):        {}\n\
+         hasher:            {} (key {:#x})\n\
+         items added:       {}\n\
+         fill ratio:        {:.4}\n\
+         estimated items:   {}\n\
+         estimated fpr:     {:.6}\n",
+        bits,
+        filter.hash_count,
+        filter.hasher.name(),
+        filter.hasher.key(),
+        filter.items,
+        fill,
+        estimate,
+        // A word that is not there finds k set bits by chance
+        fill.powi(filter.hash_count as i32)
+    );
+    out.write_all(text.as_bytes()).map_err(|x| x.to_string())?;
+    Ok(ExitCode::SUCCESS)
+}
+
+pub fn bloom(
+    args: &[String],
+    input: &mut dyn BufRead,
+    out: &mut dyn Write,
+    err: &mut dyn Write,
+) -> Result<ExitCode, String> {
+    let args = Args::parse(
+        args,
+        &["input", "fpr", "hasher", "out"],
+        &["quiet", "intersect"],
+    )?;
+    match args.positional().first().map(String::as_str) {
+        Some("build") if args.positional().len() == 1 => build(&args, input, out, err),
+        Some("add") => add(&args, input, err),
+        Some("merge") => merge(&args, out, err),
+        Some("query") => query(&args, input, out),
+        Some("info") => info(&args, out),
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+//////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+fn run(args: &[&str], stdin: &[u8]) -> Result<(ExitCode, Vec<u8>, String), String> {
+    let args: Vec<String> = args.iter().map(|x| x.to_string()).collect();
+    let mut out = Vec::new();
+    let mut err = Vec::new();
+    let code = bloom(&args, &mut &stdin[..], &mut out, &mut err)?;
+    Ok((code, out, String::from_utf8(err).unwrap()))
+}
+
+#[test]
+fn test_build_query_info() {
+    let dir = std::env::temp_dir();
+    let input = dir.join(format!("bloom_words_{}.txt", std::process::id()));
+    let output = dir.join(format!("bloom_words_{}.bloom", std::process::id()));
+    let (input, output) = (input.to_str().unwrap(), output.to_str().unwrap());
+    std::fs::write(input, "apple\r\nbanana\n\ncherry\n").unwrap();
+
+    let (code, out, err) = run(
+        &["build", "--input", input, "--fpr=0.001", "--out", output],
+        b"",
+    )
+    .unwrap();
+    assert_eq!(code, ExitCode::SUCCESS);
+    assert!(out.is_empty());
+    assert_eq!(err, "3 words, 44 bits, 10 hashes\n");
+
+    let (code, out, _) = run(&["query", output, "apple", "cherry"], b"").unwrap();
+    assert_eq!(code, ExitCode::SUCCESS);
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "apple maybe\ncherry maybe\n"
+    );
+    let (code, out, _) = run(&["query", output, "--quiet"], b"banana\ndurian\n").unwrap();
+    assert_eq!(code, ExitCode::FAILURE);
+    assert!(out.is_empty());
+    let (_, out, _) = run(&["query", output], b"banana\ndurian\n").unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), "banana maybe\ndurian no\n");
+
+    let (code, out, _) = run(&["info", output], b"").unwrap();
+    assert_eq!(code, ExitCode::SUCCESS);
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.starts_with("bits (m):          44\nhashes (k):        10\nhasher:            Murmur3 (key 0x0)\nitems added:       3\n"), "{}", out);
+    assert!(out.contains("estimated items:   3\n"), "{}", out);
+
+    // No words at all, every bit clear
+    std::fs::write(input, "").unwrap();
+    let (_, _, err) = run(&["build", "--input", input, "--out", output], b"").unwrap();
+    assert_eq!(err, "0 words, 10 bits, 7 hashes\n");
+    let (_, out, _) = run(&["info", output], b"").unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains("estimated items:   0\n"), "{}", out);
+
+    std::fs::remove_file(input).unwrap();
+    std::fs::remove_file(output).unwrap();
+}
+
+#[test]
+fn test_add_and_merge() {
+    let path = |name: &str| {
+        let path = std::env::temp_dir().join(format!("bloom_{}_{}", std::process::id(), name));
+        path.to_str().unwrap().to_string()
+    };
+    let (first, second, other, merged) = (path("a"), path("b"), path("c"), path("merged"));
+    let build = |filter: &str, words: &[u8]| {
+        run(&["build", "--input", "-", "--out", filter], words).unwrap()
+    };
+    build(&first, b"apple\nbanana\n");
+    build(&second, b"cherry\ndurian\n");
+    build(&other, b"elder\n");
+
+    let (_, _, err) = run(&["add", &first], b"fig\n").unwrap();
+    assert_eq!(err, "1 words added, 3 items\n");
+    let (_, out, _) = run(&["query", &first, "fig"], b"").unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), "fig maybe\n");
+
+    // Shards of the same size, the words of both are there
+    let (_, _, err) = run(&["merge", &first, &second, "--out", &merged], b"").unwrap();
+    assert_eq!(err, "5 items, 20 bits, 7 hashes\n");
+    let (code, _, _) = run(&["query", &merged, "apple", "cherry", "fig"], b"").unwrap();
+    assert_eq!(code, ExitCode::SUCCESS);
+    let (_, _, err) = run(
+        &["merge", &first, &second, "--intersect", "--out", &merged],
+        b"",
+    )
+    .unwrap();
+    assert_eq!(err, "0 items, 20 bits, 7 hashes\n");
+
+    assert_eq!(
+        run(&["merge", &first, &other, "--out", &merged], b"").unwrap_err(),
+        format!("{}: filters differ in bits: 20 and 10", other)
+    );
+    assert_eq!(
+        run(&["merge", &first, "--out", &merged], b"").unwrap_err(),
+        USAGE
+    );
+
+    for filename in [first, second, other, merged] {
+        std::fs::remove_file(filename).unwrap();
+    }
+}
+
+#[test]
+fn test_pipes() {
+    // Standard input to standard output, the bytes are the filter file
+    let words = crate::common::WORDS_DATA.as_bytes();
+    let (_, bytes, err) = run(
+        &["build", "--input", "-", "--hasher", "siphash", "--out", "-"],
+        words,
+    )
+    .unwrap();
+    assert_eq!(err, "338882 words, 3248204 bits, 7 hashes\n");
+    let filter = BloomFilter::from_bytes_any(&bytes).unwrap();
+    assert_eq!(filter.hasher.name(), "SipHash-2-4");
+    assert!(filter.contains("A's") && filter.contains("basic"));
+}
+
+#[test]
+fn test_bad_command_lines() {
+    let error = |args: &[&str]| run(args, b"").unwrap_err();
+    assert_eq!(error(&[]), USAGE);
+    assert_eq!(error(&["build", "--input", "x"]), USAGE);
+    assert_eq!(
+        error(&["build", "extra", "--input", "x", "--out", "y"]),
+        USAGE
+    );
+    assert_eq!(error(&["query"]), USAGE);
+    assert_eq!(error(&["info", "a", "b"]), USAGE);
+    assert_eq!(
+        error(&["build", "--input", "-", "--out", "-", "--fpr", "1"]),
+        "bad false positive rate '1'"
+    );
+    assert_eq!(
+        error(&["build", "--input", "-", "--out", "-", "--hasher", "md5"]),
+        "unknown hasher 'md5', one of fnv1a, murmur3, xxh64, siphash"
+    );
+    assert_eq!(error(&["info", "--bits"]), "unknown option '--bits'");
+
+    let missing = std::env::temp_dir().join("bloom_missing.bloom");
+    let missing = missing.to_str().unwrap();
+    assert!(error(&["info", missing]).starts_with(&format!("{}: ", missing)));
+    let not_filter = crate::common::DATA_DIR.join("weather.dat");
+    let not_filter = not_filter.to_str().unwrap();
+    assert_eq!(
+        error(&["query", not_filter, "x"]),
+        format!("{}: not a bloom filter file", not_filter)
+    );
+}
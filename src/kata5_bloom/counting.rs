@@ -0,0 +1,147 @@
+// Counting Bloom filter: a 4-bit counter instead of every bit, so that words
+// can be removed. A counter that reaches 15 stays there, as its real count is
+// lost, and removing can then never give a false negative.
+
+use super::hashers::{HashPair, Murmur3, WordHasher};
+use super::{offsets, optimal_bits, optimal_hash_count};
+
+const MAX_COUNT: u8 = 15;
+
+pub struct CountingFilter<H: WordHasher = Murmur3> {
+    // Two counters in a byte, the even one in the low half
+    counters: Vec<u8>,
+    size: usize,
+    hash_count: u32,
+    hasher: H,
+    items: usize,
+}
+
+impl<H: WordHasher> CountingFilter<H> {
+    // Sized like 'BloomFilter::new', with four times the memory
+    pub fn new(items: usize, fpr: f64, hasher: H) -> CountingFilter<H> {
+        let size = optimal_bits(items, fpr);
+        CountingFilter {
+            counters: vec![0; size.div_ceil(2)],
+            size,
+            hash_count: optimal_hash_count(size, items),
+            hasher,
+            items: 0,
+        }
+    }
+
+    fn get(&self, index: usize) -> u8 {
+        (self.counters[index / 2] >> (index % 2 * 4)) & 0xf
+    }
+
+    fn set(&mut self, index: usize, count: u8) {
+        let shift = index % 2 * 4;
+        let byte = &mut self.counters[index / 2];
+        *byte = (*byte & !(0xf << shift)) | (count << shift);
+    }
+
+    fn hash(&self, word: &str) -> HashPair {
+        self.hasher.hash_pair(word.as_bytes())
+    }
+
+    pub fn insert(&mut self, word: &str) {
+        for index in offsets(self.hash(word), self.size, self.hash_count) {
+            let count = self.get(index);
+            self.set(index, (count + 1).min(MAX_COUNT));
+        }
+        self.items += 1;
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        offsets(self.hash(word), self.size, self.hash_count).all(|index| self.get(index) > 0)
+    }
+
+    // Only words that were added can be removed, a false positive would pass
+    // the check and decrement the counters of other words instead. False if
+    // not found. Saturated counters stay, their true count is unknown.
+    pub fn remove(&mut self, word: &str) -> bool {
+        if !self.contains(word) {
+            return false;
+        }
+        for index in offsets(self.hash(word), self.size, self.hash_count) {
+            let count = self.get(index);
+            if count < MAX_COUNT {
+                self.set(index, count - 1);
+            }
+        }
+        self.items -= 1;
+        true
+    }
+
+    pub fn len(&self) -> usize {
+        self.items
+    }
+
+    pub fn memory(&self) -> usize {
+        self.counters.len()
+    }
+
+    pub fn saturated(&self) -> usize {
+        (0..self.size).filter(|&x| self.get(x) == MAX_COUNT).count()
+    }
+}
+
+//////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+use super::hashers::Fnv1a;
+#[cfg(test)]
+use crate::common::{WORDS, WORDS_DATA};
+
+#[test]
+fn test_remove() {
+    let mut filter = CountingFilter::new(WORDS.len(), 0.001, Murmur3::default());
+    for word in WORDS_DATA.lines() {
+        filter.insert(word);
+    }
+    assert_eq!(filter.len(), WORDS.len());
+    assert_eq!(filter.saturated(), 0);
+
+    let removed = ["basic", "forum", "tree", "will", "power"];
+    for word in removed {
+        assert!(filter.remove(word), "{}", word);
+        assert!(!filter.contains(word), "{}", word);
+        assert!(!filter.remove(word), "{}", word);
+    }
+    assert_eq!(filter.len(), WORDS.len() - removed.len());
+
+    // No false negatives for all the others
+    for word in WORDS_DATA.lines().filter(|x| !removed.contains(x)) {
+        assert!(filter.contains(word), "{}", word);
+    }
+}
+
+#[test]
+fn test_counters() {
+    let mut filter = CountingFilter::new(100, 0.01, Fnv1a);
+    assert_eq!(filter.memory(), filter.size.div_ceil(2));
+
+    // The same word twice needs removing twice
+    filter.insert("apple");
+    filter.insert("apple");
+    assert!(filter.remove("apple"));
+    assert!(filter.contains("apple"));
+    assert!(filter.remove("apple"));
+    assert!(!filter.contains("apple"));
+    assert!(filter.counters.iter().all(|&x| x == 0));
+
+    // Saturated counters are never decremented
+    for _ in 0..20 {
+        filter.insert("pear");
+    }
+    assert_eq!(filter.saturated(), filter.hash_count as usize);
+    for _ in 0..20 {
+        assert!(filter.remove("pear"));
+    }
+    assert!(filter.contains("pear"));
+
+    // Neighbour counters in a byte don't touch each other
+    filter.set(10, 15);
+    filter.set(11, 3);
+    filter.set(10, 4);
+    assert_eq!((filter.get(10), filter.get(11)), (4, 3));
+}
@@ -0,0 +1,213 @@
+// Cuckoo filter (Fan et al.): a 16-bit fingerprint of every word in one of
+// two buckets of 4 slots. The second bucket is the first one xor the hash of
+// the fingerprint, so that either is found from the other and a fingerprint
+// can be moved away to make room, or removed.
+
+use super::hashers::{HashPair, Murmur3, WordHasher};
+use super::membership::Membership;
+use crate::common::XorShift;
+
+const BUCKET_SIZE: usize = 4;
+const MAX_KICKS: usize = 500;
+// The load at which inserts start failing, for sizing
+const MAX_LOAD: f64 = 0.95;
+
+type Fingerprint = u16;
+
+pub struct CuckooFilter<H: WordHasher = Murmur3> {
+    // Zero for an empty slot
+    buckets: Vec<[Fingerprint; BUCKET_SIZE]>,
+    // The fingerprint left without a place by a failed insert, it is still
+    // there so that the insert has no false negative, but the filter is full
+    victim: Option<(usize, Fingerprint)>,
+    hasher: H,
+    random: XorShift,
+    items: usize,
+}
+
+impl<H: WordHasher> CuckooFilter<H> {
+    pub fn new(items: usize, hasher: H) -> CuckooFilter<H> {
+        let buckets = (items as f64 / BUCKET_SIZE as f64 / MAX_LOAD).ceil() as usize;
+        CuckooFilter {
+            buckets: vec![[0; BUCKET_SIZE]; buckets.next_power_of_two()],
+            victim: None,
+            hasher,
+            random: XorShift::new(1),
+            items: 0,
+        }
+    }
+
+    fn mask(&self) -> usize {
+        self.buckets.len() - 1
+    }
+
+    fn locate(&self, word: &str) -> (usize, Fingerprint) {
+        let (first, second): HashPair = self.hasher.hash_pair(word.as_bytes());
+        let fingerprint = (second >> 48) as Fingerprint;
+        (first as usize & self.mask(), fingerprint.max(1))
+    }
+
+    fn other_bucket(&self, index: usize, fingerprint: Fingerprint) -> usize {
+        let hash = (fingerprint as u64).wrapping_mul(0xc6a4_a793_5bd1_e995);
+        (index ^ (hash >> 32) as usize) & self.mask()
+    }
+
+    fn put(&mut self, index: usize, fingerprint: Fingerprint) -> bool {
+        match self.buckets[index].iter_mut().find(|x| **x == 0) {
+            Some(slot) => {
+                *slot = fingerprint;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // False when the filter is full, the word is not added then
+    pub fn insert(&mut self, word: &str) -> bool {
+        if self.victim.is_some() {
+            return false;
+        }
+        let (first, fingerprint) = self.locate(word);
+        let second = self.other_bucket(first, fingerprint);
+        self.items += 1;
+        if self.put(first, fingerprint) || self.put(second, fingerprint) {
+            return true;
+        }
+
+        // Kick a random fingerprint out to its other bucket, and so on
+        let mut index = [first, second][self.random.below(2) as usize];
+        let mut fingerprint = fingerprint;
+        for _ in 0..MAX_KICKS {
+            let slot = self.random.below(BUCKET_SIZE as u64) as usize;
+            std::mem::swap(&mut fingerprint, &mut self.buckets[index][slot]);
+            index = self.other_bucket(index, fingerprint);
+            if self.put(index, fingerprint) {
+                return true;
+            }
+        }
+        self.victim = Some((index, fingerprint));
+        true
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        let (first, fingerprint) = self.locate(word);
+        let second = self.other_bucket(first, fingerprint);
+        self.buckets[first].contains(&fingerprint)
+            || self.buckets[second].contains(&fingerprint)
+            || self.victim.is_some_and(|(index, victim)| {
+                victim == fingerprint && (index == first || index == second)
+            })
+    }
+}
+
+// Removal and the load, so far only in the tests
+#[cfg(test)]
+impl<H: WordHasher> CuckooFilter<H> {
+    // Only words that were added can be removed, another one with the same
+    // fingerprint and buckets would be removed instead. False if not found.
+    pub fn remove(&mut self, word: &str) -> bool {
+        let (first, fingerprint) = self.locate(word);
+        let second = self.other_bucket(first, fingerprint);
+        if let Some((index, victim)) = self.victim {
+            if victim == fingerprint && (index == first || index == second) {
+                self.victim = None;
+                self.items -= 1;
+                return true;
+            }
+        }
+        for index in [first, second] {
+            if let Some(slot) = self.buckets[index].iter_mut().find(|x| **x == fingerprint) {
+                *slot = 0;
+                self.items -= 1;
+                // There may be room for the victim now
+                if let Some((index, victim)) = self.victim {
+                    let other = self.other_bucket(index, victim);
+                    if self.put(index, victim) || self.put(other, victim) {
+                        self.victim = None;
+                    }
+                }
+                return true;
+            }
+        }
+        false
+    }
+
+    pub fn len(&self) -> usize {
+        self.items
+    }
+
+    pub fn load(&self) -> f64 {
+        self.items as f64 / (self.buckets.len() * BUCKET_SIZE) as f64
+    }
+}
+
+impl<H: WordHasher> Membership for CuckooFilter<H> {
+    fn name(&self) -> &'static str {
+        "Cuckoo"
+    }
+
+    fn contains(&self, word: &str) -> bool {
+        CuckooFilter::contains(self, word)
+    }
+
+    fn bits(&self) -> usize {
+        self.buckets.len() * BUCKET_SIZE * Fingerprint::BITS as usize
+    }
+}
+
+//////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+use super::non_words;
+#[cfg(test)]
+use crate::common::{WORDS, WORDS_DATA};
+
+#[test]
+fn test_cuckoo() {
+    let mut filter = CuckooFilter::new(WORDS.len(), Murmur3::default());
+    assert!(WORDS_DATA.lines().all(|x| filter.insert(x)));
+    assert_eq!(filter.len(), WORDS.len());
+    assert!(filter.victim.is_none());
+    assert!(WORDS.iter().all(|x| filter.contains(x)));
+
+    // 8 fingerprints to compare, 2^16 values: about 0.012%
+    let probes = non_words(100_000, 11);
+    let hits = probes.iter().filter(|x| filter.contains(x)).count();
+    assert!(hits < 30, "{}", hits);
+
+    let removed = ["basic", "forum", "tree"];
+    for word in removed {
+        assert!(filter.remove(word));
+        assert!(!filter.contains(word), "{}", word);
+    }
+    assert_eq!(filter.len(), WORDS.len() - removed.len());
+    assert!((WORDS_DATA.lines())
+        .filter(|x| !removed.contains(x))
+        .all(|x| filter.contains(x)));
+}
+
+#[test]
+fn test_cuckoo_full() {
+    // 2 buckets of 4 slots
+    let mut filter = CuckooFilter::new(5, Murmur3::default());
+    assert_eq!(filter.buckets.len(), 2);
+    let mut added = Vec::new();
+    for i in 0.. {
+        let word = format!("word {}", i);
+        if !filter.insert(&word) {
+            break;
+        }
+        added.push(word);
+    }
+    // The last one is the victim, all of them are still there
+    assert_eq!(added.len(), 9);
+    assert!(filter.victim.is_some());
+    assert!(added.iter().all(|x| filter.contains(x)));
+    assert!(!filter.contains("another"));
+
+    // Removing makes room for the victim
+    assert!(filter.remove(&added[0]));
+    assert!(filter.victim.is_none());
+    assert_eq!(filter.load(), 1.0);
+    assert!(added[1..].iter().all(|x| filter.contains(x)));
+}
@@ -0,0 +1,61 @@
+use std::fmt;
+
+#[derive(Debug)]
+pub enum BloomError {
+    Io(std::io::Error),
+    BadMagic,
+    Version(u16),
+    Hasher {
+        expected: (u8, u128),
+        found: (u8, u128),
+    },
+    Header(String),
+    Checksum,
+    // Filters combined with 'union' or 'intersection'
+    Mismatch {
+        field: &'static str,
+        left: u128,
+        right: u128,
+    },
+}
+
+impl fmt::Display for BloomError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BloomError::Io(error) => write!(f, "{}", error),
+            BloomError::BadMagic => write!(f, "not a bloom filter file"),
+            BloomError::Version(version) => write!(f, "unsupported version {}", version),
+            BloomError::Hasher { expected, found } => write!(
+                f,
+                "filter hashed with hasher {} key {:#x}, expected hasher {} key {:#x}",
+                found.0, found.1, expected.0, expected.1
+            ),
+            BloomError::Header(message) => write!(f, "bad header: {}", message),
+            BloomError::Checksum => write!(f, "checksum mismatch"),
+            BloomError::Mismatch { field, left, right } => {
+                write!(f, "filters differ in {}: {} and {}", field, left, right)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BloomError {}
+
+impl From<std::io::Error> for BloomError {
+    fn from(error: std::io::Error) -> Self {
+        BloomError::Io(error)
+    }
+}
+
+#[test]
+fn test_messages() {
+    assert_eq!(BloomError::Version(7).to_string(), "unsupported version 7");
+    let hasher = BloomError::Hasher {
+        expected: (2, 0),
+        found: (4, 0x10),
+    };
+    assert_eq!(
+        hasher.to_string(),
+        "filter hashed with hasher 4 key 0x10, expected hasher 2 key 0x0"
+    );
+}
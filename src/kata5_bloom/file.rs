@@ -0,0 +1,221 @@
+// Saved filter, all numbers little-endian:
+//
+//   offset  size  field
+//        0     8  magic "BLOOMFLT"
+//        8     2  version, 1
+//       10     1  hasher id, see 'WordHasher::id'
+//       11     1  reserved, 0
+//       12     4  k, the number of hashes
+//       16     8  m, the number of bits
+//       24     8  number of items added
+//       32    16  hasher key or seed, see 'WordHasher::key'
+//       48     n  the bits, n = (m + 7) / 8, bit i is bit (7 - i % 8) of byte i / 8
+//   48 + n     8  FNV-1a 64 of all the bytes before it
+
+use super::error::BloomError;
+use super::hashers::{AnyHasher, Fnv1a, WordHasher};
+use super::BloomFilter;
+
+use bit_vec::BitVec;
+use std::path::Path;
+
+const MAGIC: &[u8; 8] = b"BLOOMFLT";
+const VERSION: u16 = 1;
+const HEADER_SIZE: usize = 48;
+
+fn field<const N: usize>(bytes: &[u8], offset: usize) -> [u8; N] {
+    bytes[offset..offset + N].try_into().unwrap()
+}
+
+impl<H: WordHasher> BloomFilter<H> {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_SIZE + self.bools.len() / 8 + 9);
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+        bytes.extend_from_slice(&[self.hasher.id(), 0]);
+        bytes.extend_from_slice(&self.hash_count.to_le_bytes());
+        bytes.extend_from_slice(&(self.bools.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(&(self.items as u64).to_le_bytes());
+        bytes.extend_from_slice(&self.hasher.key().to_le_bytes());
+        bytes.extend_from_slice(&self.bools.to_bytes());
+        let checksum = Fnv1a.hash(&bytes);
+        bytes.extend_from_slice(&checksum.to_le_bytes());
+        bytes
+    }
+
+    // The hasher must be the one the filter was built with, id and key included
+    pub fn from_bytes(bytes: &[u8], hasher: H) -> Result<BloomFilter<H>, BloomError> {
+        if bytes.len() < MAGIC.len() || &bytes[..MAGIC.len()] != MAGIC {
+            return Err(BloomError::BadMagic);
+        }
+        if bytes.len() < HEADER_SIZE {
+            return Err(BloomError::Header(format!("{} bytes only", bytes.len())));
+        }
+        let version = u16::from_le_bytes(field(bytes, 8));
+        if version != VERSION {
+            return Err(BloomError::Version(version));
+        }
+
+        let bits = u64::from_le_bytes(field(bytes, 16));
+        let size = (bits.div_ceil(8) as usize)
+            .checked_add(HEADER_SIZE + 8)
+            .filter(|&size| size == bytes.len())
+            .ok_or_else(|| BloomError::Header(format!("{} bits in {} bytes", bits, bytes.len())))?;
+        let (content, checksum) = bytes.split_at(size - 8);
+        if Fnv1a.hash(content) != u64::from_le_bytes(field(checksum, 0)) {
+            return Err(BloomError::Checksum);
+        }
+
+        let found = (bytes[10], u128::from_le_bytes(field(bytes, 32)));
+        let expected = (hasher.id(), hasher.key());
+        if found != expected {
+            return Err(BloomError::Hasher { expected, found });
+        }
+        let hash_count = u32::from_le_bytes(field(bytes, 12));
+        if bits == 0 || hash_count == 0 {
+            return Err(BloomError::Header(format!(
+                "{} bits, {} hashes",
+                bits, hash_count
+            )));
+        }
+
+        let mut bools = BitVec::from_bytes(&content[HEADER_SIZE..]);
+        bools.truncate(bits as usize);
+        Ok(BloomFilter {
+            bools,
+            hash_count,
+            hasher,
+            items: u64::from_le_bytes(field(bytes, 24)) as usize,
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), BloomError> {
+        Ok(std::fs::write(path, self.to_bytes())?)
+    }
+
+    #[cfg(test)]
+    pub fn load(path: &Path, hasher: H) -> Result<BloomFilter<H>, BloomError> {
+        BloomFilter::from_bytes(&std::fs::read(path)?, hasher)
+    }
+}
+
+impl BloomFilter<AnyHasher> {
+    // With the hasher the header names
+    pub fn from_bytes_any(bytes: &[u8]) -> Result<BloomFilter<AnyHasher>, BloomError> {
+        let hasher = match bytes.get(..HEADER_SIZE) {
+            Some(header) if header.starts_with(MAGIC) => {
+                let (id, key) = (header[10], u128::from_le_bytes(field(header, 32)));
+                AnyHasher::from_id(id, key).ok_or_else(|| {
+                    BloomError::Header(format!("unknown hasher {} with key {:#x}", id, key))
+                })?
+            }
+            // Fails with the right error
+            _ => AnyHasher::from_id(1, 0).unwrap(),
+        };
+        BloomFilter::from_bytes(bytes, hasher)
+    }
+}
+
+//////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+use super::hashers::{Murmur3, SipHash24, XxHash64};
+#[cfg(test)]
+use super::{get_hashes, non_words};
+#[cfg(test)]
+use crate::common::{WORDS, WORDS_DATA};
+
+#[test]
+fn test_round_trip() {
+    let hasher = SipHash24 { key: (1, 2) };
+    let filter = BloomFilter::new(WORDS.len(), 0.01, hasher, &get_hashes(&hasher, &WORDS_DATA));
+    let filename = std::env::temp_dir().join(format!("bloom_{}.bin", std::process::id()));
+    filter.save(&filename).unwrap();
+    let loaded = BloomFilter::load(&filename, hasher).unwrap();
+    std::fs::remove_file(&filename).unwrap();
+
+    assert_eq!(loaded.bools, filter.bools);
+    assert_eq!(loaded.hash_count, filter.hash_count);
+    assert_eq!(loaded.items, WORDS.len());
+    assert!(WORDS.iter().all(|word| loaded.contains(word)));
+    for word in non_words(10_000, 3) {
+        assert_eq!(loaded.contains(&word), filter.contains(&word), "{}", word);
+    }
+
+    // Odd sizes keep the padding of the last byte out of the filter
+    let filter = BloomFilter::with_size(
+        13,
+        2,
+        Murmur3::default(),
+        &get_hashes(&Murmur3::default(), "a\nb\nc"),
+    );
+    let bytes = filter.to_bytes();
+    assert_eq!(bytes.len(), HEADER_SIZE + 2 + 8);
+    assert_eq!(&bytes[..12], b"BLOOMFLT\x01\x00\x02\x00");
+    let loaded = BloomFilter::from_bytes(&bytes, Murmur3::default()).unwrap();
+    assert_eq!(loaded.bools, filter.bools);
+    assert_eq!(loaded.to_bytes(), bytes);
+}
+
+#[test]
+fn test_any_hasher_files() {
+    let hasher = XxHash64 { seed: 42 };
+    let filter = BloomFilter::with_size(1000, 4, hasher, &get_hashes(&hasher, "one\ntwo"));
+    let loaded = BloomFilter::from_bytes_any(&filter.to_bytes()).unwrap();
+    assert_eq!(loaded.hasher.name(), "XXH64");
+    assert_eq!(loaded.hasher.key(), 42);
+    assert!(loaded.contains("one") && loaded.contains("two"));
+    assert_eq!(loaded.to_bytes(), filter.to_bytes());
+
+    let mut bytes = filter.to_bytes();
+    bytes[10] = 9;
+    assert_eq!(
+        BloomFilter::from_bytes_any(&bytes)
+            .err()
+            .unwrap()
+            .to_string(),
+        "bad header: unknown hasher 9 with key 0x2a"
+    );
+    assert_eq!(
+        BloomFilter::from_bytes_any(b"BLOOM")
+            .err()
+            .unwrap()
+            .to_string(),
+        "not a bloom filter file"
+    );
+}
+
+#[test]
+fn test_bad_files() {
+    let hasher = Murmur3::default();
+    let bytes = BloomFilter::with_size(100, 3, hasher, &get_hashes(&hasher, "x\ny")).to_bytes();
+    let load = |bytes: &[u8]| {
+        BloomFilter::from_bytes(bytes, hasher)
+            .err()
+            .unwrap()
+            .to_string()
+    };
+
+    assert_eq!(load(b"BLOOM"), "not a bloom filter file");
+    assert_eq!(load(b"BLOOMFLT\x01\x00"), "bad header: 10 bytes only");
+    assert_eq!(
+        load(&bytes[..bytes.len() - 1]),
+        "bad header: 100 bits in 68 bytes"
+    );
+
+    let mut changed = bytes.clone();
+    changed[8] = 2;
+    assert_eq!(load(&changed), "unsupported version 2");
+    let mut changed = bytes.clone();
+    changed[50] ^= 1;
+    assert_eq!(load(&changed), "checksum mismatch");
+
+    let other = Murmur3 { seed: 5 };
+    assert_eq!(
+        BloomFilter::from_bytes(&bytes, other)
+            .err()
+            .unwrap()
+            .to_string(),
+        "filter hashed with hasher 2 key 0x0, expected hasher 2 key 0x5"
+    );
+}
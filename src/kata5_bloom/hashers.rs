@@ -0,0 +1,474 @@
+// Hash functions with a specified output, unlike 'DefaultHasher' which may
+// change between Rust releases and so can't be used for a saved filter
+
+pub type HashType = u64;
+
+// Two independent base hashes, the k hashes of the filter are made of them
+pub type HashPair = (HashType, HashType);
+
+pub trait WordHasher {
+    fn name(&self) -> &'static str;
+
+    // Saved with a filter, so that it's read back only with the same hashing
+    fn id(&self) -> u8;
+    fn key(&self) -> u128 {
+        0
+    }
+
+    fn hash(&self, bytes: &[u8]) -> HashType;
+
+    // A 64-bit hash gives the second one through the Murmur3 finalizer
+    fn hash_pair(&self, bytes: &[u8]) -> HashPair {
+        let first = self.hash(bytes);
+        (first, fmix64(first ^ 0x9e37_79b9_7f4a_7c15))
+    }
+}
+
+pub(super) fn fmix64(mut k: u64) -> u64 {
+    k ^= k >> 33;
+    k = k.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    k ^= k >> 33;
+    k = k.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    k ^ (k >> 33)
+}
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    u64::from_le_bytes(bytes[..8].try_into().unwrap())
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes(bytes[..4].try_into().unwrap())
+}
+
+// Up to 8 bytes, little-endian
+fn read_partial(bytes: &[u8]) -> u64 {
+    (bytes.iter().rev()).fold(0, |word, &byte| (word << 8) | byte as u64)
+}
+
+//////////////////////////////////////////////////////////////////////////////
+// FNV-1a, 64 bits
+
+#[derive(Copy, Clone, Default, Debug)]
+pub struct Fnv1a;
+
+impl WordHasher for Fnv1a {
+    fn name(&self) -> &'static str {
+        "FNV-1a"
+    }
+
+    fn id(&self) -> u8 {
+        1
+    }
+
+    fn hash(&self, bytes: &[u8]) -> HashType {
+        bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+        })
+    }
+}
+
+//////////////////////////////////////////////////////////////////////////////
+// MurmurHash3 x64 128, both halves of the result are used as the pair
+
+#[derive(Copy, Clone, Default, Debug)]
+pub struct Murmur3 {
+    pub seed: u32,
+}
+
+const MURMUR_C1: u64 = 0x87c3_7b91_1142_53d5;
+const MURMUR_C2: u64 = 0x4cf5_ad43_2745_937f;
+
+impl Murmur3 {
+    fn hash128(&self, bytes: &[u8]) -> (u64, u64) {
+        let mix_k1 = |k1: u64| {
+            k1.wrapping_mul(MURMUR_C1)
+                .rotate_left(31)
+                .wrapping_mul(MURMUR_C2)
+        };
+        let mix_k2 = |k2: u64| {
+            k2.wrapping_mul(MURMUR_C2)
+                .rotate_left(33)
+                .wrapping_mul(MURMUR_C1)
+        };
+
+        let (mut h1, mut h2) = (self.seed as u64, self.seed as u64);
+        let mut blocks = bytes.chunks_exact(16);
+        for block in &mut blocks {
+            h1 ^= mix_k1(read_u64(block));
+            h1 = h1.rotate_left(27).wrapping_add(h2);
+            h1 = h1.wrapping_mul(5).wrapping_add(0x52dc_e729);
+            h2 ^= mix_k2(read_u64(&block[8..]));
+            h2 = h2.rotate_left(31).wrapping_add(h1);
+            h2 = h2.wrapping_mul(5).wrapping_add(0x3849_5ab5);
+        }
+
+        // The tail of up to 15 bytes, little-endian
+        let tail = blocks.remainder();
+        if tail.len() > 8 {
+            h2 ^= mix_k2(read_partial(&tail[8..]));
+        }
+        if !tail.is_empty() {
+            h1 ^= mix_k1(read_partial(&tail[..tail.len().min(8)]));
+        }
+
+        let len = bytes.len() as u64;
+        h1 ^= len;
+        h2 ^= len;
+        h1 = h1.wrapping_add(h2);
+        h2 = h2.wrapping_add(h1);
+        h1 = fmix64(h1);
+        h2 = fmix64(h2);
+        h1 = h1.wrapping_add(h2);
+        h2 = h2.wrapping_add(h1);
+        (h1, h2)
+    }
+}
+
+impl WordHasher for Murmur3 {
+    fn name(&self) -> &'static str {
+        "Murmur3"
+    }
+
+    fn id(&self) -> u8 {
+        2
+    }
+
+    fn key(&self) -> u128 {
+        self.seed.into()
+    }
+
+    fn hash(&self, bytes: &[u8]) -> HashType {
+        self.hash128(bytes).0
+    }
+
+    fn hash_pair(&self, bytes: &[u8]) -> HashPair {
+        self.hash128(bytes)
+    }
+}
+
+//////////////////////////////////////////////////////////////////////////////
+// xxHash, XXH64
+
+#[derive(Copy, Clone, Default, Debug)]
+pub struct XxHash64 {
+    pub seed: u64,
+}
+
+const XXH_PRIME_1: u64 = 0x9e37_79b1_85eb_ca87;
+const XXH_PRIME_2: u64 = 0xc2b2_ae3d_27d4_eb4f;
+const XXH_PRIME_3: u64 = 0x1656_67b1_9e37_79f9;
+const XXH_PRIME_4: u64 = 0x85eb_ca77_c2b2_ae63;
+const XXH_PRIME_5: u64 = 0x27d4_eb2f_1656_67c5;
+
+fn xxh_round(acc: u64, input: u64) -> u64 {
+    acc.wrapping_add(input.wrapping_mul(XXH_PRIME_2))
+        .rotate_left(31)
+        .wrapping_mul(XXH_PRIME_1)
+}
+
+fn xxh_merge(hash: u64, acc: u64) -> u64 {
+    (hash ^ xxh_round(0, acc))
+        .wrapping_mul(XXH_PRIME_1)
+        .wrapping_add(XXH_PRIME_4)
+}
+
+impl WordHasher for XxHash64 {
+    fn name(&self) -> &'static str {
+        "XXH64"
+    }
+
+    fn id(&self) -> u8 {
+        3
+    }
+
+    fn key(&self) -> u128 {
+        self.seed.into()
+    }
+
+    fn hash(&self, bytes: &[u8]) -> HashType {
+        let seed = self.seed;
+        let mut stripes = bytes.chunks_exact(32);
+        let mut hash = if bytes.len() >= 32 {
+            let mut acc = [
+                seed.wrapping_add(XXH_PRIME_1).wrapping_add(XXH_PRIME_2),
+                seed.wrapping_add(XXH_PRIME_2),
+                seed,
+                seed.wrapping_sub(XXH_PRIME_1),
+            ];
+            for stripe in &mut stripes {
+                for (i, lane) in acc.iter_mut().enumerate() {
+                    *lane = xxh_round(*lane, read_u64(&stripe[i * 8..]));
+                }
+            }
+            let hash = acc[0]
+                .rotate_left(1)
+                .wrapping_add(acc[1].rotate_left(7))
+                .wrapping_add(acc[2].rotate_left(12))
+                .wrapping_add(acc[3].rotate_left(18));
+            acc.iter().fold(hash, |hash, &lane| xxh_merge(hash, lane))
+        } else {
+            seed.wrapping_add(XXH_PRIME_5)
+        };
+        hash = hash.wrapping_add(bytes.len() as u64);
+
+        let mut rest = stripes.remainder();
+        while rest.len() >= 8 {
+            hash ^= xxh_round(0, read_u64(rest));
+            hash = hash
+                .rotate_left(27)
+                .wrapping_mul(XXH_PRIME_1)
+                .wrapping_add(XXH_PRIME_4);
+            rest = &rest[8..];
+        }
+        if rest.len() >= 4 {
+            hash ^= (read_u32(rest) as u64).wrapping_mul(XXH_PRIME_1);
+            hash = hash
+                .rotate_left(23)
+                .wrapping_mul(XXH_PRIME_2)
+                .wrapping_add(XXH_PRIME_3);
+            rest = &rest[4..];
+        }
+        for &byte in rest {
+            hash ^= (byte as u64).wrapping_mul(XXH_PRIME_5);
+            hash = hash.rotate_left(11).wrapping_mul(XXH_PRIME_1);
+        }
+
+        hash ^= hash >> 33;
+        hash = hash.wrapping_mul(XXH_PRIME_2);
+        hash ^= hash >> 29;
+        hash = hash.wrapping_mul(XXH_PRIME_3);
+        hash ^ (hash >> 32)
+    }
+}
+
+//////////////////////////////////////////////////////////////////////////////
+// SipHash-2-4 with a 128-bit key, the only one here that resists chosen words
+
+#[derive(Copy, Clone, Default, Debug)]
+pub struct SipHash24 {
+    pub key: (u64, u64),
+}
+
+fn sip_round(v: &mut [u64; 4]) {
+    v[0] = v[0].wrapping_add(v[1]);
+    v[1] = v[1].rotate_left(13) ^ v[0];
+    v[0] = v[0].rotate_left(32);
+    v[2] = v[2].wrapping_add(v[3]);
+    v[3] = v[3].rotate_left(16) ^ v[2];
+    v[0] = v[0].wrapping_add(v[3]);
+    v[3] = v[3].rotate_left(21) ^ v[0];
+    v[2] = v[2].wrapping_add(v[1]);
+    v[1] = v[1].rotate_left(17) ^ v[2];
+    v[2] = v[2].rotate_left(32);
+}
+
+impl WordHasher for SipHash24 {
+    fn name(&self) -> &'static str {
+        "SipHash-2-4"
+    }
+
+    fn id(&self) -> u8 {
+        4
+    }
+
+    fn key(&self) -> u128 {
+        (self.key.1 as u128) << 64 | self.key.0 as u128
+    }
+
+    fn hash(&self, bytes: &[u8]) -> HashType {
+        let (k0, k1) = self.key;
+        let mut v = [
+            k0 ^ 0x736f_6d65_7073_6575,
+            k1 ^ 0x646f_7261_6e64_6f6d,
+            k0 ^ 0x6c79_6765_6e65_7261,
+            k1 ^ 0x7465_6462_7974_6573,
+        ];
+        let mut compress = |m: u64, rounds: usize| {
+            v[3] ^= m;
+            for _ in 0..rounds {
+                sip_round(&mut v);
+            }
+            v[0] ^= m;
+        };
+
+        let mut words = bytes.chunks_exact(8);
+        for word in &mut words {
+            compress(read_u64(word), 2);
+        }
+        // The last word holds the rest of the bytes and the length in the top byte
+        let last = read_partial(words.remainder()) | (bytes.len() as u64) << 56;
+        compress(last, 2);
+
+        v[2] ^= 0xff;
+        for _ in 0..4 {
+            sip_round(&mut v);
+        }
+        v[0] ^ v[1] ^ v[2] ^ v[3]
+    }
+}
+
+//////////////////////////////////////////////////////////////////////////////
+// Any of the hashers, as chosen by name or read from a saved filter
+
+#[derive(Copy, Clone, Debug)]
+pub enum AnyHasher {
+    Fnv1a(Fnv1a),
+    Murmur3(Murmur3),
+    XxHash64(XxHash64),
+    SipHash24(SipHash24),
+}
+
+impl AnyHasher {
+    pub const NAMES: [&'static str; 4] = ["fnv1a", "murmur3", "xxh64", "siphash"];
+
+    // With the default seed or key
+    pub fn from_name(name: &str) -> Option<AnyHasher> {
+        match name {
+            "fnv1a" => Some(AnyHasher::Fnv1a(Fnv1a)),
+            "murmur3" => Some(AnyHasher::Murmur3(Murmur3::default())),
+            "xxh64" => Some(AnyHasher::XxHash64(XxHash64::default())),
+            "siphash" => Some(AnyHasher::SipHash24(SipHash24::default())),
+            _ => None,
+        }
+    }
+
+    // None for an unknown id or a key that does not fit the hasher
+    pub fn from_id(id: u8, key: u128) -> Option<AnyHasher> {
+        match id {
+            1 if key == 0 => Some(AnyHasher::Fnv1a(Fnv1a)),
+            2 => Some(AnyHasher::Murmur3(Murmur3 {
+                seed: key.try_into().ok()?,
+            })),
+            3 => Some(AnyHasher::XxHash64(XxHash64 {
+                seed: key.try_into().ok()?,
+            })),
+            4 => Some(AnyHasher::SipHash24(SipHash24 {
+                key: (key as u64, (key >> 64) as u64),
+            })),
+            _ => None,
+        }
+    }
+
+    fn inner(&self) -> &dyn WordHasher {
+        match self {
+            AnyHasher::Fnv1a(hasher) => hasher,
+            AnyHasher::Murmur3(hasher) => hasher,
+            AnyHasher::XxHash64(hasher) => hasher,
+            AnyHasher::SipHash24(hasher) => hasher,
+        }
+    }
+}
+
+impl WordHasher for AnyHasher {
+    fn name(&self) -> &'static str {
+        self.inner().name()
+    }
+
+    fn id(&self) -> u8 {
+        self.inner().id()
+    }
+
+    fn key(&self) -> u128 {
+        self.inner().key()
+    }
+
+    fn hash(&self, bytes: &[u8]) -> HashType {
+        self.inner().hash(bytes)
+    }
+
+    fn hash_pair(&self, bytes: &[u8]) -> HashPair {
+        self.inner().hash_pair(bytes)
+    }
+}
+
+#[cfg(test)]
+const SIP_KEY: (u64, u64) = (0x0706_0504_0302_0100, 0x0f0e_0d0c_0b0a_0908);
+
+#[test]
+fn test_fnv1a() {
+    assert_eq!(Fnv1a.hash(b""), 0xcbf2_9ce4_8422_2325);
+    assert_eq!(Fnv1a.hash(b"a"), 0xaf63_dc4c_8601_ec8c);
+    assert_eq!(Fnv1a.hash(b"hello"), 0xa430_d846_80aa_bd0b);
+}
+
+#[test]
+fn test_murmur3() {
+    let murmur = Murmur3::default();
+    assert_eq!(murmur.hash_pair(b""), (0, 0));
+    assert_eq!(
+        murmur.hash_pair(b"hello"),
+        (0xcbd8_a7b3_41bd_9b02, 0x5b1e_906a_48ae_1d19)
+    );
+    assert_eq!(
+        murmur.hash_pair(b"The quick brown fox jumps over the lazy dog"),
+        (0xe34b_bc7b_bc07_1b6c, 0x7a43_3ca9_c49a_9347)
+    );
+    assert_eq!(murmur.hash(b"a"), 0x8555_5565_f659_7889);
+    assert_ne!(Murmur3 { seed: 1 }.hash(b"a"), murmur.hash(b"a"));
+}
+
+#[test]
+fn test_xxhash64() {
+    let xxh = XxHash64::default();
+    assert_eq!(xxh.hash(b""), 0xef46_db37_51d8_e999);
+    assert_eq!(xxh.hash(b"a"), 0xd24e_c4f1_a98c_6e5b);
+    assert_eq!(
+        xxh.hash(b"The quick brown fox jumps over the lazy dog"),
+        0x0b24_2d36_1fda_71bc
+    );
+}
+
+#[test]
+fn test_siphash24() {
+    let sip = SipHash24 { key: SIP_KEY };
+    assert_eq!(sip.hash(b""), 0x726f_db47_dd0e_0e31);
+    assert_eq!(sip.hash(b"a"), 0x2ba3_e8e9_a711_48ca);
+    // The last vector of the reference implementation, 63 bytes 00..3e
+    let bytes: Vec<u8> = (0..63).collect();
+    assert_eq!(sip.hash(&bytes), 0x958a_324c_eb06_4572);
+}
+
+#[test]
+fn test_hash_pairs() {
+    // Every length around the block sizes, the second hash differs from the first
+    let hashers: [&dyn WordHasher; 4] = [
+        &Fnv1a,
+        &Murmur3::default(),
+        &XxHash64::default(),
+        &SipHash24 { key: SIP_KEY },
+    ];
+    let bytes: Vec<u8> = (0..70).collect();
+    for hasher in hashers {
+        let mut seen = std::collections::HashSet::new();
+        for len in 1..bytes.len() {
+            let (first, second) = hasher.hash_pair(&bytes[..len]);
+            assert_eq!(first, hasher.hash(&bytes[..len]), "{}", hasher.name());
+            assert_ne!(first, second, "{}", hasher.name());
+            assert!(seen.insert(first), "{} {}", hasher.name(), len);
+        }
+    }
+}
+
+#[test]
+fn test_any_hasher() {
+    for name in AnyHasher::NAMES {
+        let hasher = AnyHasher::from_name(name).unwrap();
+        let back = AnyHasher::from_id(hasher.id(), hasher.key()).unwrap();
+        assert_eq!(
+            back.hash_pair(b"word"),
+            hasher.hash_pair(b"word"),
+            "{}",
+            name
+        );
+    }
+    assert!(AnyHasher::from_name("md5").is_none());
+
+    let sip = AnyHasher::from_id(4, SipHash24 { key: SIP_KEY }.key()).unwrap();
+    assert_eq!(sip.hash(b""), 0x726f_db47_dd0e_0e31);
+    assert_eq!(sip.name(), "SipHash-2-4");
+    let murmur = AnyHasher::from_id(2, 7).unwrap();
+    assert_eq!(murmur.hash_pair(b"a"), Murmur3 { seed: 7 }.hash_pair(b"a"));
+    assert!(AnyHasher::from_id(2, 1 << 32).is_none());
+    assert!(AnyHasher::from_id(1, 1).is_none());
+    assert!(AnyHasher::from_id(9, 0).is_none());
+}
@@ -0,0 +1,58 @@
+// What all the filters answer, for comparing them on the same words
+
+use super::counting::CountingFilter;
+use super::hashers::WordHasher;
+use super::scalable::ScalableFilter;
+use super::BloomFilter;
+
+pub trait Membership {
+    fn name(&self) -> &'static str;
+
+    // False positives possible, false negatives never
+    fn contains(&self, word: &str) -> bool;
+
+    // Memory of the table, without the few fields around it
+    fn bits(&self) -> usize;
+}
+
+impl<H: WordHasher> Membership for BloomFilter<H> {
+    fn name(&self) -> &'static str {
+        "Bloom"
+    }
+
+    fn contains(&self, word: &str) -> bool {
+        BloomFilter::contains(self, word)
+    }
+
+    fn bits(&self) -> usize {
+        self.bools.len()
+    }
+}
+
+impl<H: WordHasher> Membership for CountingFilter<H> {
+    fn name(&self) -> &'static str {
+        "Counting"
+    }
+
+    fn contains(&self, word: &str) -> bool {
+        CountingFilter::contains(self, word)
+    }
+
+    fn bits(&self) -> usize {
+        self.memory() * 8
+    }
+}
+
+impl<H: WordHasher + Clone> Membership for ScalableFilter<H> {
+    fn name(&self) -> &'static str {
+        "Scalable"
+    }
+
+    fn contains(&self, word: &str) -> bool {
+        ScalableFilter::contains(self, word)
+    }
+
+    fn bits(&self) -> usize {
+        ScalableFilter::bits(self)
+    }
+}
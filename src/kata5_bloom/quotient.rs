@@ -0,0 +1,225 @@
+// Quotient filter (Bender et al.): a q + r bit fingerprint is stored as its r
+// bit remainder, in the slot of its q bit quotient or after it. The slots of a
+// quotient make a run, sorted runs of neighbour quotients make a cluster.
+// Three bits of every slot tell where the runs are:
+//   occupied      some fingerprint has the quotient of this slot
+//   continuation  the slot is in the same run as the one before it
+//   shifted       the remainder is not in the slot of its quotient
+
+use super::hashers::{Murmur3, WordHasher};
+use super::membership::Membership;
+
+const OCCUPIED: u32 = 1;
+const CONTINUATION: u32 = 2;
+const SHIFTED: u32 = 4;
+const METADATA_BITS: u32 = 3;
+// Clusters get long above that, and inserts and lookups slow
+const MAX_LOAD: f64 = 0.75;
+
+pub struct QuotientFilter<H: WordHasher = Murmur3> {
+    // The metadata bits with the remainder above them
+    slots: Vec<u32>,
+    remainder_bits: u32,
+    hasher: H,
+    items: usize,
+}
+
+impl<H: WordHasher> QuotientFilter<H> {
+    // A lookup compares against about one remainder, fpr = 2^-r
+    pub fn new(items: usize, fpr: f64, hasher: H) -> QuotientFilter<H> {
+        let slots = ((items as f64 / MAX_LOAD).ceil() as usize).next_power_of_two();
+        let remainder_bits = (-fpr.log2()).ceil().clamp(1.0, 29.0) as u32;
+        QuotientFilter {
+            slots: vec![0; slots],
+            remainder_bits,
+            hasher,
+            items: 0,
+        }
+    }
+
+    fn split(&self, word: &str) -> (usize, u32) {
+        let hash = self.hasher.hash(word.as_bytes());
+        let remainder = hash as u32 & ((1 << self.remainder_bits) - 1);
+        let quotient = (hash >> self.remainder_bits) as usize & (self.slots.len() - 1);
+        (quotient, remainder)
+    }
+
+    fn next(&self, index: usize) -> usize {
+        (index + 1) & (self.slots.len() - 1)
+    }
+
+    fn previous(&self, index: usize) -> usize {
+        index.wrapping_sub(1) & (self.slots.len() - 1)
+    }
+
+    fn is(&self, index: usize, flag: u32) -> bool {
+        self.slots[index] & flag != 0
+    }
+
+    // Walks back to the start of the cluster, then forward over the runs of
+    // the occupied quotients before this one
+    fn run_start(&self, quotient: usize) -> usize {
+        let mut bucket = quotient;
+        while self.is(bucket, SHIFTED) {
+            bucket = self.previous(bucket);
+        }
+        let mut start = bucket;
+        while bucket != quotient {
+            start = self.next(start);
+            while self.is(start, CONTINUATION) {
+                start = self.next(start);
+            }
+            bucket = self.next(bucket);
+            while !self.is(bucket, OCCUPIED) {
+                bucket = self.next(bucket);
+            }
+        }
+        start
+    }
+
+    // Shifts the slots from the index on by one, up to the first empty one.
+    // The occupied bits belong to the slots and stay.
+    fn shift_in(&mut self, mut index: usize, mut entry: u32) {
+        loop {
+            let mut previous = self.slots[index];
+            let empty = previous == 0;
+            if !empty {
+                previous |= SHIFTED;
+                if previous & OCCUPIED != 0 {
+                    entry |= OCCUPIED;
+                    previous &= !OCCUPIED;
+                }
+            }
+            self.slots[index] = entry;
+            if empty {
+                return;
+            }
+            entry = previous;
+            index = self.next(index);
+        }
+    }
+
+    // False when the filter is full. A fingerprint that is there already is
+    // not stored again.
+    pub fn insert(&mut self, word: &str) -> bool {
+        if self.items == self.slots.len() {
+            return false;
+        }
+        let (quotient, remainder) = self.split(word);
+        let mut entry = remainder << METADATA_BITS;
+        if self.slots[quotient] == 0 {
+            self.slots[quotient] = entry | OCCUPIED;
+            self.items += 1;
+            return true;
+        }
+
+        let was_occupied = self.is(quotient, OCCUPIED);
+        self.slots[quotient] |= OCCUPIED;
+        let start = self.run_start(quotient);
+        let mut index = start;
+        if was_occupied {
+            // The place in the sorted run
+            loop {
+                let stored = self.slots[index] >> METADATA_BITS;
+                if stored == remainder {
+                    return true;
+                }
+                if stored > remainder {
+                    break;
+                }
+                index = self.next(index);
+                if !self.is(index, CONTINUATION) {
+                    break;
+                }
+            }
+            if index == start {
+                self.slots[start] |= CONTINUATION;
+            } else {
+                entry |= CONTINUATION;
+            }
+        }
+        if index != quotient {
+            entry |= SHIFTED;
+        }
+        self.shift_in(index, entry);
+        self.items += 1;
+        true
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        let (quotient, remainder) = self.split(word);
+        if !self.is(quotient, OCCUPIED) {
+            return false;
+        }
+        let mut index = self.run_start(quotient);
+        loop {
+            if self.slots[index] >> METADATA_BITS == remainder {
+                return true;
+            }
+            index = self.next(index);
+            if !self.is(index, CONTINUATION) {
+                return false;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+impl<H: WordHasher> QuotientFilter<H> {
+    pub fn len(&self) -> usize {
+        self.items
+    }
+}
+
+impl<H: WordHasher> Membership for QuotientFilter<H> {
+    fn name(&self) -> &'static str {
+        "Quotient"
+    }
+
+    fn contains(&self, word: &str) -> bool {
+        QuotientFilter::contains(self, word)
+    }
+
+    fn bits(&self) -> usize {
+        self.slots.len() * (self.remainder_bits + METADATA_BITS) as usize
+    }
+}
+
+//////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+use super::hashers::Fnv1a;
+#[cfg(test)]
+use super::non_words;
+#[cfg(test)]
+use crate::common::{WORDS, WORDS_DATA};
+
+#[test]
+fn test_quotient() {
+    let mut filter = QuotientFilter::new(WORDS.len(), 0.01, Murmur3::default());
+    assert_eq!((filter.slots.len(), filter.remainder_bits), (1 << 19, 7));
+    assert!(WORDS_DATA.lines().all(|x| filter.insert(x)));
+    // Words with the same 26-bit fingerprint are stored once, n^2 / 2^27 of them
+    assert!(filter.len() > WORDS.len() - 1000, "{}", filter.len());
+    assert!(WORDS.iter().all(|x| filter.contains(x)));
+
+    // The run of a quotient is about as long as the load, 0.65
+    let probes = non_words(100_000, 13);
+    let rate = probes.iter().filter(|x| filter.contains(x)).count() as f64 / 1e5;
+    assert!(0.003 < rate && rate < 0.008, "{}", rate);
+}
+
+#[test]
+fn test_quotient_full() {
+    // Every slot taken wraps the clusters around the end of the table
+    let mut filter = QuotientFilter::new(12, 0.001, Fnv1a);
+    assert_eq!(filter.slots.len(), 16);
+    let words: Vec<String> = (0..16).map(|i| format!("word {}", i)).collect();
+    for word in &words {
+        assert!(filter.insert(word));
+    }
+    assert_eq!(filter.len(), 16);
+    assert!(!filter.insert("one more"));
+    assert!(words.iter().all(|x| filter.contains(x)));
+    assert!(filter.slots.iter().all(|&x| x != 0));
+}
@@ -0,0 +1,137 @@
+// Scalable Bloom filter (Almeida et al.): when a filter is full, the next one
+// is twice as large and has half its false positive rate. The rates sum up as
+// p0 * (1 + r + r^2 + ...) = p0 / (1 - r), so that for the rate p the first
+// filter gets p * (1 - r) and the expected rate stays below p for any number
+// of items.
+
+use super::hashers::{Murmur3, WordHasher};
+use super::BloomFilter;
+
+const GROWTH: usize = 2;
+const TIGHTENING: f64 = 0.5;
+
+pub struct ScalableFilter<H: WordHasher + Clone = Murmur3> {
+    // Each one with the number of items it is sized for and its rate
+    filters: Vec<(BloomFilter<H>, usize, f64)>,
+    initial_capacity: usize,
+    fpr: f64,
+    hasher: H,
+}
+
+impl<H: WordHasher + Clone> ScalableFilter<H> {
+    pub fn new(initial_capacity: usize, fpr: f64, hasher: H) -> ScalableFilter<H> {
+        ScalableFilter {
+            filters: Vec::new(),
+            initial_capacity: initial_capacity.max(1),
+            fpr,
+            hasher,
+        }
+    }
+
+    fn add_filter(&mut self) {
+        let (capacity, fpr) = match self.filters.last() {
+            Some((_, capacity, fpr)) => (capacity * GROWTH, fpr * TIGHTENING),
+            None => (self.initial_capacity, self.fpr * (1.0 - TIGHTENING)),
+        };
+        let filter = BloomFilter::new(capacity, fpr, self.hasher.clone(), &[]);
+        self.filters.push((filter, capacity, fpr));
+    }
+
+    // A word that seems to be there already is not added, so that it doesn't
+    // fill the filter. False if so.
+    pub fn insert(&mut self, word: &str) -> bool {
+        if self.contains(word) {
+            return false;
+        }
+        if self
+            .filters
+            .last()
+            .is_none_or(|(filter, capacity, _)| filter.items >= *capacity)
+        {
+            self.add_filter();
+        }
+        self.filters.last_mut().unwrap().0.insert(word);
+        true
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        // The last one is the largest, the most words are there
+        (self.filters.iter().rev()).any(|(filter, _, _)| filter.contains(word))
+    }
+
+    pub fn len(&self) -> usize {
+        self.filters.iter().map(|(filter, _, _)| filter.items).sum()
+    }
+
+    pub fn filter_count(&self) -> usize {
+        self.filters.len()
+    }
+
+    pub fn bits(&self) -> usize {
+        self.filters
+            .iter()
+            .map(|(filter, _, _)| filter.bools.len())
+            .sum()
+    }
+
+    // False positive when any of the filters has one: 1 - (1 - p0)(1 - p1)...
+    // A full filter measures a bit above its rate, so this is no hard bound
+    pub fn expected_fpr(&self) -> f64 {
+        1.0 - (self.filters.iter())
+            .map(|(_, _, fpr)| 1.0 - fpr)
+            .product::<f64>()
+    }
+}
+
+//////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+use super::non_words;
+#[cfg(test)]
+use crate::common::{WORDS, WORDS_DATA};
+
+#[test]
+fn test_growth() {
+    let mut filter = ScalableFilter::new(1000, 0.01, Murmur3::default());
+    assert_eq!(
+        (filter.len(), filter.filter_count(), filter.expected_fpr()),
+        (0, 0, 0.0)
+    );
+    assert!(!filter.contains("basic"));
+
+    let mut added = 0;
+    for word in WORDS_DATA.lines() {
+        added += filter.insert(word) as usize;
+        assert!(filter.expected_fpr() < 0.01);
+    }
+    assert_eq!(filter.len(), added);
+    // Up to 1% of the words are false positives of the filters before them
+    assert!(added > WORDS.len() - WORDS.len() / 100, "{}", added);
+    // 1000 + 2000 + ... + 128000 is not enough, with 256000 more it is
+    assert_eq!(filter.filter_count(), 9);
+    assert!(!filter.insert("basic"));
+
+    assert!(WORDS.iter().all(|x| filter.contains(x)));
+    let probes = non_words(100_000, 7);
+    let rate = probes.iter().filter(|x| filter.contains(x)).count() as f64 / 1e5;
+    // Up to three standard deviations of the sampling noise above the expected rate
+    let expected = filter.expected_fpr();
+    let slack = 3.0 * (expected * (1.0 - expected) / 1e5).sqrt();
+    assert!(rate < expected + slack, "{} {}", rate, expected);
+}
+
+#[test]
+fn test_sub_filters() {
+    let mut filter = ScalableFilter::new(10, 0.02, Murmur3::default());
+    for i in 0..70 {
+        filter.insert(&format!("item {}", i));
+    }
+    let sizes: Vec<(usize, f64)> = (filter.filters.iter())
+        .map(|(_, capacity, fpr)| (*capacity, *fpr))
+        .collect();
+    assert_eq!(sizes, [(10, 0.01), (20, 0.005), (40, 0.0025)]);
+    assert_eq!(filter.filters[0].0.items, 10);
+    assert_eq!(filter.filters[1].0.items, 20);
+    assert!(filter.filters[2].0.items > 30);
+    assert!((0..70).all(|i| filter.contains(&format!("item {}", i))));
+}
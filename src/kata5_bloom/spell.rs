@@ -0,0 +1,392 @@
+// Spell checker: the filter rejects most misspelled words at once, without
+// false negatives. What it accepts may be checked against the word list.
+
+use super::cli::parse_fpr;
+use super::get_hashes;
+use super::hashers::Murmur3;
+use super::BloomFilter;
+use crate::cli::Args;
+use crate::common::{WORDS, WORDS_DATA, WORDS_PLAIN};
+
+use std::io::{BufRead, Write};
+use std::process::ExitCode;
+
+pub const USAGE: &str = "\
+Usage: codekata spell [options] < TEXT
+
+Prints the misspelled words of the standard input as LINE:COLUMN: WORD,
+with suggestions, and fails if there are any.
+
+Options:
+  --fpr P          false positive rate of the filter, 0.01 by default
+  --confirm        check the words the filter accepts against the word list
+  --suggestions N  up to N suggestions a word, 5 by default, 0 for none";
+
+const MAX_DISTANCE: usize = 2;
+
+#[derive(Debug, PartialEq)]
+pub struct Token<'a> {
+    pub word: &'a str,
+    pub line: usize,
+    // In characters, from 1
+    pub column: usize,
+}
+
+// Letters with apostrophes between them, like "A's" or "can't". The quotes
+// around a word are not a part of it.
+pub fn tokenize(text: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    for (line_index, line) in text.lines().enumerate() {
+        let mut start = None;
+        let chars: Vec<(usize, char)> = line.char_indices().collect();
+        for (column, &(offset, c)) in chars.iter().enumerate() {
+            let inner_apostrophe = is_apostrophe(c)
+                && start.is_some()
+                && chars.get(column + 1).is_some_and(|x| x.1.is_alphabetic());
+            if c.is_alphabetic() || inner_apostrophe {
+                start.get_or_insert((column, offset));
+                continue;
+            }
+            if let Some((start_column, start_offset)) = start.take() {
+                tokens.push(Token {
+                    word: &line[start_offset..offset],
+                    line: line_index + 1,
+                    column: start_column + 1,
+                });
+            }
+        }
+        if let Some((start_column, start_offset)) = start {
+            tokens.push(Token {
+                word: &line[start_offset..],
+                line: line_index + 1,
+                column: start_column + 1,
+            });
+        }
+    }
+    tokens
+}
+
+fn is_apostrophe(c: char) -> bool {
+    c == '\'' || c == '\u{2019}'
+}
+
+// The forms of a word that make it right: as written, in lower case for the
+// start of a sentence or shouting, and capitalized for "PARIS"
+fn spellings(word: &str) -> Vec<String> {
+    let word = word.replace('\u{2019}', "'");
+    let lower = word.to_lowercase();
+    let mut chars = lower.chars();
+    let capitalized: String = chars
+        .next()
+        .into_iter()
+        .flat_map(char::to_uppercase)
+        .chain(chars)
+        .collect();
+    let mut forms = vec![word];
+    for form in [lower, capitalized] {
+        if !forms.contains(&form) {
+            forms.push(form);
+        }
+    }
+    forms
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Verdict {
+    Right,
+    Wrong,
+    // Accepted by the filter, but not in the word list
+    FalsePositive,
+}
+
+pub struct SpellChecker {
+    filter: BloomFilter<Murmur3>,
+}
+
+impl SpellChecker {
+    pub fn new(fpr: f64) -> SpellChecker {
+        let hasher = Murmur3::default();
+        let hashes = get_hashes(&hasher, &WORDS_DATA);
+        SpellChecker {
+            filter: BloomFilter::new(hashes.len(), fpr, hasher, &hashes),
+        }
+    }
+
+    pub fn check(&self, word: &str, confirm: bool) -> Verdict {
+        let forms = spellings(word);
+        if !forms.iter().any(|x| self.filter.contains(x)) {
+            Verdict::Wrong
+        } else if confirm && !forms.iter().any(|x| WORDS.contains(x.as_str())) {
+            Verdict::FalsePositive
+        } else {
+            Verdict::Right
+        }
+    }
+
+    // The closest words of the list up to 2 edits away, ignoring case. Of
+    // the same distance, the ones in lower case come first, then the ones
+    // with the same letters like "the" for "teh", with the same first
+    // letter, and of the same length.
+    pub fn suggest(&self, word: &str, count: usize) -> Vec<&'static str> {
+        let word: Vec<char> = word
+            .replace('\u{2019}', "'")
+            .to_lowercase()
+            .chars()
+            .collect();
+        let letters = |x: &[char]| {
+            let mut letters = x.to_vec();
+            letters.sort_unstable();
+            letters
+        };
+        let word_letters = letters(&word);
+        let mut found = Vec::new();
+        for &candidate in WORDS_PLAIN.iter() {
+            // Most words are too long or too short, they are skipped before
+            // the lower case copy. Lower case has as many letters in the list.
+            let length_difference = candidate.chars().count().abs_diff(word.len());
+            if length_difference > MAX_DISTANCE {
+                continue;
+            }
+            let lower: Vec<char> = candidate.to_lowercase().chars().collect();
+            let distance = edit_distance(&word, &lower);
+            if distance <= MAX_DISTANCE {
+                found.push((
+                    distance,
+                    candidate.chars().any(char::is_uppercase),
+                    letters(&lower) != word_letters,
+                    lower.first() != word.first(),
+                    length_difference,
+                    candidate,
+                ));
+            }
+        }
+        found.sort();
+        found.into_iter().take(count).map(|x| x.5).collect()
+    }
+}
+
+// Damerau-Levenshtein, the optimal string alignment one: a swap of two
+// neighbours is a single edit, as in "teh"
+fn edit_distance(left: &[char], right: &[char]) -> usize {
+    let width = right.len() + 1;
+    let mut rows = vec![0; (left.len() + 1) * width];
+    for (i, row) in rows.chunks_mut(width).enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in rows[..width].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=left.len() {
+        for j in 1..=right.len() {
+            let cost = usize::from(left[i - 1] != right[j - 1]);
+            let mut best = (rows[(i - 1) * width + j] + 1)
+                .min(rows[i * width + j - 1] + 1)
+                .min(rows[(i - 1) * width + j - 1] + cost);
+            if i > 1 && j > 1 && left[i - 1] == right[j - 2] && left[i - 2] == right[j - 1] {
+                best = best.min(rows[(i - 2) * width + j - 2] + 1);
+            }
+            rows[i * width + j] = best;
+        }
+    }
+    rows[left.len() * width + right.len()]
+}
+
+pub fn spell(
+    args: &[String],
+    input: &mut dyn BufRead,
+    out: &mut dyn Write,
+    err: &mut dyn Write,
+) -> Result<ExitCode, String> {
+    let args = Args::parse(args, &["fpr", "suggestions"], &["confirm"])?;
+    if !args.positional().is_empty() {
+        return Err(USAGE.to_string());
+    }
+    let fpr = parse_fpr(args.option("fpr"), 0.01)?;
+    let suggestions = match args.option("suggestions") {
+        Some(text) => text
+            .parse::<usize>()
+            .map_err(|_| format!("bad number of suggestions '{}'", text))?,
+        None => 5,
+    };
+
+    let mut text = String::new();
+    input.read_to_string(&mut text).map_err(|x| x.to_string())?;
+    let checker = SpellChecker::new(fpr);
+    let write_error = |x: std::io::Error| x.to_string();
+    let tokens = tokenize(&text);
+    let (mut wrong, mut false_positives) = (0, 0);
+    for token in &tokens {
+        let verdict = checker.check(token.word, args.flag("confirm"));
+        if verdict == Verdict::Right {
+            continue;
+        }
+        wrong += 1;
+        if verdict == Verdict::FalsePositive {
+            false_positives += 1;
+        }
+        write!(out, "{}:{}: {}", token.line, token.column, token.word).map_err(write_error)?;
+        // Suggesting goes through the whole list, only when they are printed
+        if suggestions > 0 {
+            let words = checker.suggest(token.word, suggestions);
+            if !words.is_empty() {
+                write!(out, " -> {}", words.join(", ")).map_err(write_error)?;
+            }
+        }
+        writeln!(out).map_err(write_error)?;
+    }
+
+    write!(err, "{} words, {} misspelled", tokens.len(), wrong).map_err(write_error)?;
+    if args.flag("confirm") {
+        write!(err, ", {} of them passed the filter", false_positives).map_err(write_error)?;
+    }
+    writeln!(err).map_err(write_error)?;
+    Ok(if wrong == 0 {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+//////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_tokenize() {
+    let tokens = tokenize("It's 'quoted', A's\n  dogs' tails--and can\u{2019}t 42x");
+    let words: Vec<(&str, usize, usize)> =
+        tokens.iter().map(|x| (x.word, x.line, x.column)).collect();
+    assert_eq!(
+        words,
+        [
+            ("It's", 1, 1),
+            ("quoted", 1, 7),
+            ("A's", 1, 16),
+            ("dogs", 2, 3),
+            ("tails", 2, 9),
+            ("and", 2, 16),
+            ("can\u{2019}t", 2, 20),
+            ("x", 2, 28),
+        ]
+    );
+    assert_eq!(
+        tokenize("Ardèche's café"),
+        [
+            Token {
+                word: "Ardèche's",
+                line: 1,
+                column: 1
+            },
+            Token {
+                word: "café",
+                line: 1,
+                column: 11
+            },
+        ]
+    );
+    assert!(tokenize(" ' -- 12 ").is_empty());
+}
+
+#[test]
+fn test_spellings() {
+    assert_eq!(spellings("the"), ["the", "The"]);
+    assert_eq!(spellings("The"), ["The", "the"]);
+    assert_eq!(spellings("PARIS"), ["PARIS", "paris", "Paris"]);
+    assert_eq!(spellings("CAN\u{2019}T"), ["CAN'T", "can't", "Can't"]);
+}
+
+#[test]
+fn test_edit_distance() {
+    let distance = |a: &str, b: &str| {
+        edit_distance(
+            &a.chars().collect::<Vec<_>>(),
+            &b.chars().collect::<Vec<_>>(),
+        )
+    };
+    assert_eq!(distance("kitten", "sitting"), 3);
+    assert_eq!(distance("teh", "the"), 1);
+    assert_eq!(distance("", "abc"), 3);
+    assert_eq!(distance("abc", ""), 3);
+    assert_eq!(distance("café", "cafe"), 1);
+    assert_eq!(distance("ca", "abc"), 3);
+}
+
+#[test]
+fn test_checker() {
+    let checker = SpellChecker::new(0.01);
+    for word in [
+        "basic",
+        "Basic",
+        "BASIC",
+        "A's",
+        "Paris",
+        "PARIS",
+        "can\u{2019}t",
+        "I",
+    ] {
+        assert_eq!(checker.check(word, true), Verdict::Right, "{}", word);
+    }
+    assert_eq!(checker.check("a's", true), Verdict::Right); // "a's" is a word too
+    for word in ["teh", "basicc", "Pariss", "fooom"] {
+        assert_eq!(checker.check(word, false), Verdict::Wrong, "{}", word);
+    }
+
+    // Some of the non-words get through the filter, the word list stops them
+    let passed: Vec<String> = super::non_words(2000, 19)
+        .into_iter()
+        .filter(|x| spellings(x).iter().all(|x| !WORDS.contains(x.as_str())))
+        .filter(|x| checker.check(x, false) == Verdict::Right)
+        .collect();
+    assert!(!passed.is_empty());
+    assert!(passed
+        .iter()
+        .all(|x| checker.check(x, true) == Verdict::FalsePositive));
+
+    assert_eq!(checker.suggest("teh", 3), ["the", "eth", "tea"]);
+    assert_eq!(
+        checker.suggest("Jumpd", 4),
+        ["jumps", "jumpy", "jump", "jumped"]
+    );
+    assert_eq!(
+        checker.suggest("recieve", 3),
+        ["receive", "relieve", "recurve"]
+    );
+    assert!(checker.suggest("xqzkjw", 5).is_empty());
+}
+
+#[test]
+fn test_command_line() {
+    let run = |args: &[&str], text: &str| {
+        let args: Vec<String> = args.iter().map(|x| x.to_string()).collect();
+        let mut out = Vec::new();
+        let mut err = Vec::new();
+        let code = spell(&args, &mut text.as_bytes(), &mut out, &mut err)?;
+        let text = |x: Vec<u8>| String::from_utf8(x).unwrap();
+        Ok::<_, String>((code, text(out), text(err)))
+    };
+
+    let (code, out, err) = run(
+        &["--suggestions", "2"],
+        "The quick brown fox\njumpd over teh dog.",
+    )
+    .unwrap();
+    assert_eq!(code, ExitCode::FAILURE);
+    assert_eq!(out, "2:1: jumpd -> jumps, jumpy\n2:12: teh -> the, eth\n");
+    assert_eq!(err, "8 words, 2 misspelled\n");
+
+    let (code, out, err) = run(&["--confirm"], "It's Paris, isn't it?").unwrap();
+    assert_eq!(code, ExitCode::SUCCESS);
+    assert_eq!(out, "");
+    assert_eq!(err, "4 words, 0 misspelled, 0 of them passed the filter\n");
+
+    let (_, out, _) = run(&["--suggestions", "0"], "qqqq").unwrap();
+    assert_eq!(out, "1:1: qqqq\n");
+
+    assert_eq!(
+        run(&["--fpr", "2"], "").unwrap_err(),
+        "bad false positive rate '2'"
+    );
+    assert_eq!(
+        run(&["--suggestions", "x"], "").unwrap_err(),
+        "bad number of suggestions 'x'"
+    );
+    assert_eq!(run(&["file.txt"], "").unwrap_err(), USAGE);
+}
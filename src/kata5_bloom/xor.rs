@@ -0,0 +1,152 @@
+// Xor filter (Graf and Lemire): a static table of 8-bit values, where the
+// fingerprint of a word is the xor of the three values at its three places,
+// one in each third of the table. The table is built by peeling: a place
+// used by a single word is set last, so any value works for the others.
+
+use super::hashers::{fmix64, Murmur3, WordHasher};
+use super::membership::Membership;
+
+type Fingerprint = u8;
+
+pub struct XorFilter<H: WordHasher = Murmur3> {
+    fingerprints: Vec<Fingerprint>,
+    block: usize,
+    seed: u64,
+    hasher: H,
+}
+
+fn fingerprint(hash: u64) -> Fingerprint {
+    (hash ^ (hash >> 32)) as Fingerprint
+}
+
+// Lemire's fast range: 32 bits of the hash scaled to [0, block)
+fn places(hash: u64, block: usize) -> [usize; 3] {
+    [0, 1, 2].map(|i| {
+        let bits = hash.rotate_left(21 * i as u32) as u32 as u64;
+        ((bits * block as u64) >> 32) as usize + i * block
+    })
+}
+
+impl<H: WordHasher> XorFilter<H> {
+    pub fn new<'a>(words: impl IntoIterator<Item = &'a str>, hasher: H) -> XorFilter<H> {
+        // A repeated word would never be alone in a place
+        let mut keys: Vec<u64> = (words.into_iter())
+            .map(|x| hasher.hash(x.as_bytes()))
+            .collect();
+        keys.sort_unstable();
+        keys.dedup();
+
+        let block = (32 + (keys.len() as f64 * 1.23).ceil() as usize).div_ceil(3);
+        for seed in 1.. {
+            if let Some(fingerprints) = XorFilter::<H>::build(&keys, block, seed) {
+                return XorFilter {
+                    fingerprints,
+                    block,
+                    seed,
+                    hasher,
+                };
+            }
+        }
+        unreachable!()
+    }
+
+    // None when the peeling gets stuck, a few percent of the seeds
+    fn build(keys: &[u64], block: usize, seed: u64) -> Option<Vec<Fingerprint>> {
+        // Xor and number of the hashes at every place
+        let mut sets = vec![(0u64, 0u32); block * 3];
+        for &key in keys {
+            // The Murmur3 finalizer gives a new hash for every seed
+            let hash = fmix64(key.wrapping_add(seed));
+            for place in places(hash, block) {
+                sets[place].0 ^= hash;
+                sets[place].1 += 1;
+            }
+        }
+
+        let mut alone: Vec<usize> = (0..sets.len()).filter(|&x| sets[x].1 == 1).collect();
+        let mut peeled = Vec::with_capacity(keys.len());
+        while let Some(place) = alone.pop() {
+            if sets[place].1 != 1 {
+                continue;
+            }
+            let hash = sets[place].0;
+            peeled.push((hash, place));
+            for other in places(hash, block) {
+                sets[other].0 ^= hash;
+                sets[other].1 -= 1;
+                if sets[other].1 == 1 {
+                    alone.push(other);
+                }
+            }
+        }
+        if peeled.len() < keys.len() {
+            return None;
+        }
+
+        let mut fingerprints = vec![0; block * 3];
+        for &(hash, place) in peeled.iter().rev() {
+            let [a, b, c] = places(hash, block);
+            fingerprints[place] =
+                fingerprint(hash) ^ fingerprints[a] ^ fingerprints[b] ^ fingerprints[c];
+        }
+        Some(fingerprints)
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        let hash = fmix64(self.hasher.hash(word.as_bytes()).wrapping_add(self.seed));
+        let [a, b, c] = places(hash, self.block);
+        fingerprint(hash) == self.fingerprints[a] ^ self.fingerprints[b] ^ self.fingerprints[c]
+    }
+}
+
+impl<H: WordHasher> Membership for XorFilter<H> {
+    fn name(&self) -> &'static str {
+        "Xor"
+    }
+
+    fn contains(&self, word: &str) -> bool {
+        XorFilter::contains(self, word)
+    }
+
+    fn bits(&self) -> usize {
+        self.fingerprints.len() * Fingerprint::BITS as usize
+    }
+}
+
+//////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+use super::hashers::XxHash64;
+#[cfg(test)]
+use super::non_words;
+#[cfg(test)]
+use crate::common::{WORDS, WORDS_DATA};
+
+#[test]
+fn test_xor() {
+    let filter = XorFilter::new(WORDS_DATA.lines(), XxHash64::default());
+    assert!(WORDS.iter().all(|x| filter.contains(x)));
+    // 1.23 bytes a word
+    let bits_per_item = filter.bits() as f64 / WORDS.len() as f64;
+    assert!(
+        9.8 < bits_per_item && bits_per_item < 9.9,
+        "{}",
+        bits_per_item
+    );
+
+    // 2^-8, about 0.39%
+    let probes = non_words(100_000, 17);
+    let rate = probes.iter().filter(|x| filter.contains(x)).count() as f64 / 1e5;
+    assert!(0.0033 < rate && rate < 0.0045, "{}", rate);
+}
+
+#[test]
+fn test_xor_small() {
+    let empty = XorFilter::new([], Murmur3::default());
+    assert_eq!(empty.fingerprints.len(), 33);
+    assert!(!empty.contains("a") || !empty.contains("b"));
+
+    let words = ["a", "b", "a", "c"];
+    let filter = XorFilter::new(words, Murmur3::default());
+    assert!(words.iter().all(|x| filter.contains(x)));
+}
@@ -20,12 +20,12 @@ fn is_two_words(word: &str) -> usize {
             }
         }
     }
-    return 0;
+    0
 }
 
 #[allow(dead_code)]
 fn find_subwords_readable() -> usize {
-    words_with_len(6).map(|w| is_two_words(w)).sum()
+    words_with_len(6).map(is_two_words).sum()
 }
 
 // Fast variant //
@@ -40,7 +40,7 @@ fn is_two_words_fast(word: &str) -> usize {
             }
         }
     }
-    return 0;
+    0
 }
 
 fn find_subwords_fast() -> usize {
@@ -72,7 +72,7 @@ fn is_two_words_fast_ext(
             }
         }
     }
-    return 0;
+    0
 }
 
 #[test]
@@ -1,3 +1,4 @@
+mod cli;
 mod common;
 mod kata1_pricing;
 mod kata2_chop;
@@ -6,8 +7,38 @@ mod kata5_bloom;
 mod kata6_anagrams;
 mod kata8_objectives;
 
-fn main() {
-    kata5_bloom::print_stats();
+use std::process::ExitCode;
 
-    println!("\nRun 'cargo test' to execute all samples.");
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match args.first().map(String::as_str) {
+        None => {
+            kata5_bloom::print_stats();
+            println!("\nRun 'cargo test' to execute all samples.");
+            Ok(ExitCode::SUCCESS)
+        }
+        Some("munge") => {
+            kata4_munging::munge(&args[1..], &mut std::io::stdout(), &mut std::io::stderr())
+        }
+        Some("spell") => kata5_bloom::spell(
+            &args[1..],
+            &mut std::io::stdin().lock(),
+            &mut std::io::stdout(),
+            &mut std::io::stderr(),
+        ),
+        Some("bloom") => kata5_bloom::bloom(
+            &args[1..],
+            &mut std::io::stdin().lock(),
+            &mut std::io::stdout(),
+            &mut std::io::stderr(),
+        ),
+        Some(other) => Err(format!(
+            "unknown command '{}'\n\nUsage: codekata [munge ... | spell ... | bloom ...]",
+            other
+        )),
+    };
+    result.unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        ExitCode::FAILURE
+    })
 }